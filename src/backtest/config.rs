@@ -0,0 +1,102 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// One strategy parameter set. A config file holds a single object or an array of them.
+#[derive(Deserialize, Clone, Debug)]
+pub struct BacktestConfig {
+    pub name: String,
+    pub size_sol: f64,
+    #[serde(default)]
+    pub entry: EntryFilter,
+    #[serde(default)]
+    pub fee_tier: FeeTier,
+    #[serde(default)]
+    pub exit: ExitRules,
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct EntryFilter {
+    /// Bounds on the creator's own buy in the create transaction, in SOL.
+    #[serde(default)]
+    pub min_dev_buy_sol: f64,
+    pub max_dev_buy_sol: Option<f64>,
+    /// Symbols (case-insensitive) that are never bought.
+    #[serde(default)]
+    pub symbol_deny: Vec<String>,
+    /// Most other wallets that may have bought in the create slot. Checked
+    /// when the buy comes due, so it only sees buys up to then.
+    pub max_block0_snipers: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FeeTier {
+    /// Priority fee paid per landed transaction, in lamports.
+    #[serde(default)]
+    pub priority_fee_lamports: u64,
+    /// Chance that our buy lands at all at this tier.
+    #[serde(default = "default_land_probability")]
+    pub land_probability: f64,
+    /// Slots between the create and our buy landing.
+    #[serde(default = "default_slot_delay")]
+    pub slot_delay: u64,
+}
+
+impl Default for FeeTier {
+    fn default() -> Self {
+        FeeTier {
+            priority_fee_lamports: 0,
+            land_probability: default_land_probability(),
+            slot_delay: default_slot_delay(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExitRules {
+    #[serde(default = "default_hold_secs")]
+    pub hold_secs: i64,
+    /// e.g. 0.5 exits once the position is worth 150% of its cost.
+    pub take_profit_pct: Option<f64>,
+    /// e.g. 0.3 exits once the position is worth 70% of its cost.
+    pub stop_loss_pct: Option<f64>,
+}
+
+impl Default for ExitRules {
+    fn default() -> Self {
+        ExitRules {
+            hold_secs: default_hold_secs(),
+            take_profit_pct: None,
+            stop_loss_pct: None,
+        }
+    }
+}
+
+fn default_seed() -> u64 {
+    42
+}
+
+fn default_land_probability() -> f64 {
+    1.0
+}
+
+fn default_slot_delay() -> u64 {
+    1
+}
+
+fn default_hold_secs() -> i64 {
+    10
+}
+
+pub fn load_configs(path: &Path) -> Result<Vec<BacktestConfig>, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+    if let Ok(configs) = serde_json::from_str::<Vec<BacktestConfig>>(&raw) {
+        return Ok(configs);
+    }
+    serde_json::from_str::<BacktestConfig>(&raw)
+        .map(|config| vec![config])
+        .map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+}
@@ -0,0 +1,46 @@
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::utils::pf_events::{decode_pump_events, PumpEvent};
+use crate::utils::recorder::read_recording;
+
+/// The decoded pump.fun events of one recorded notification.
+pub struct RecordedMessage {
+    pub slot: u64,
+    pub events: Vec<PumpEvent>,
+}
+
+/// Loads recordings in the order given, keeping only notifications that carry pump.fun events.
+pub fn load_messages(paths: &[PathBuf]) -> std::io::Result<Vec<RecordedMessage>> {
+    let mut messages = Vec::new();
+
+    for path in paths {
+        for (_, msg) in read_recording(path)? {
+            let parsed: Value = match serde_json::from_str(&msg) {
+                Ok(parsed) => parsed,
+                Err(_) => continue,
+            };
+            let logs: Vec<String> = match parsed["params"]["result"]["value"]["logs"].as_array() {
+                Some(logs) => logs
+                    .iter()
+                    .map(|v| v.as_str().unwrap_or_default().to_string())
+                    .collect(),
+                None => continue,
+            };
+
+            let events = decode_pump_events(&logs);
+            if events.is_empty() {
+                continue;
+            }
+
+            messages.push(RecordedMessage {
+                slot: parsed["params"]["result"]["context"]["slot"]
+                    .as_u64()
+                    .unwrap_or(0),
+                events,
+            });
+        }
+    }
+
+    Ok(messages)
+}
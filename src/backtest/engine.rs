@@ -0,0 +1,336 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+use super::config::BacktestConfig;
+use super::dataset::RecordedMessage;
+use super::metrics::{compute_metrics, Metrics};
+use crate::utils::constants::LAMPORTS_PER_SOL;
+use crate::utils::pf_events::PumpEvent;
+use crate::utils::pf_price::{buy_fee, curve_buy_tokens, curve_sell_sol};
+use crate::utils::snipers::{SniperBoard, SniperConfig};
+
+// base signature fee paid by every landed transaction
+const BASE_FEE_LAMPORTS: u64 = 5_000;
+
+#[derive(Clone, Debug)]
+pub struct TradeResult {
+    pub mint: Pubkey,
+    pub create_slot: u64,
+    pub entry_slot: u64,
+    pub entry_ts: i64,
+    pub exit_ts: i64,
+    pub tokens: u64,
+    pub cost_lamports: u64,
+    pub proceeds_lamports: u64,
+    pub fees_lamports: u64,
+    pub pnl_lamports: i64,
+    pub exit_reason: &'static str,
+}
+
+pub struct BacktestReport {
+    pub name: String,
+    pub candidates: usize,
+    pub missed: usize,
+    pub trades: Vec<TradeResult>,
+    pub metrics: Metrics,
+}
+
+struct PendingEntry {
+    create_slot: u64,
+    target_slot: u64,
+}
+
+struct OpenPosition {
+    create_slot: u64,
+    entry_slot: u64,
+    entry_ts: i64,
+    tokens: u64,
+    cost_lamports: u64,
+}
+
+/// Small deterministic generator so a seed always reproduces the same landings.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Runs one config over the recorded messages. Our own buys are priced against
+/// the recorded curve but are not fed back into it, so the impact of large
+/// sizes on later traders is not modelled.
+pub fn run_backtest(config: &BacktestConfig, messages: &[RecordedMessage]) -> BacktestReport {
+    let mut rng = XorShift(config.seed.max(1));
+    let size_lamports = (config.size_sol * LAMPORTS_PER_SOL as f64) as u64;
+    let tx_fee = BASE_FEE_LAMPORTS + config.fee_tier.priority_fee_lamports;
+
+    // only the create slot matters for the entry filter
+    let snipers = SniperBoard::new(SniperConfig {
+        window_slots: 1,
+        flush_dir: None,
+    });
+    let mut curves: HashMap<Pubkey, (u64, u64)> = HashMap::new();
+    let mut pending: HashMap<Pubkey, PendingEntry> = HashMap::new();
+    let mut open: HashMap<Pubkey, OpenPosition> = HashMap::new();
+    let mut trades = Vec::new();
+    let mut candidates = 0;
+    let mut missed = 0;
+    let mut now_ts = 0;
+
+    let close = |mint: Pubkey, position: OpenPosition, curve: (u64, u64), now_ts: i64, reason| {
+        let proceeds = curve_sell_sol(curve.0, curve.1, position.tokens);
+        let fees = tx_fee * 2;
+        TradeResult {
+            mint,
+            create_slot: position.create_slot,
+            entry_slot: position.entry_slot,
+            entry_ts: position.entry_ts,
+            exit_ts: now_ts,
+            tokens: position.tokens,
+            cost_lamports: position.cost_lamports,
+            proceeds_lamports: proceeds,
+            fees_lamports: fees,
+            pnl_lamports: proceeds as i64 - position.cost_lamports as i64 - fees as i64,
+            exit_reason: reason,
+        }
+    };
+
+    for message in messages {
+        // buys whose landing slot has been reached execute against the last known curve
+        let due: Vec<Pubkey> = pending
+            .iter()
+            .filter(|(_, entry)| message.slot >= entry.target_slot)
+            .map(|(mint, _)| *mint)
+            .collect();
+        for mint in due {
+            let entry = pending.remove(&mint).unwrap();
+            let crowded = config
+                .entry
+                .max_block0_snipers
+                .is_some_and(|max| snipers.snipers_in_block0(&mint) > max);
+            if crowded {
+                // filtered out after all, not a candidate
+                candidates -= 1;
+                continue;
+            }
+            if let Some((v_sol, v_tokens)) = curves.get(&mint) {
+                let tokens = curve_buy_tokens(*v_sol, *v_tokens, size_lamports);
+                if tokens > 0 {
+                    open.insert(
+                        mint,
+                        OpenPosition {
+                            create_slot: entry.create_slot,
+                            entry_slot: message.slot,
+                            entry_ts: now_ts,
+                            tokens,
+                            // the pump.fun fee comes on top of what goes into the curve
+                            cost_lamports: size_lamports + buy_fee(size_lamports),
+                        },
+                    );
+                }
+            }
+        }
+
+        for (idx, event) in message.events.iter().enumerate() {
+            match event {
+                PumpEvent::Create(create_event) => {
+                    snipers.on_create(create_event.mint, create_event.user, message.slot);
+                    // the creator's own buy rides in the same transaction
+                    let dev_buy = message.events[idx + 1..].iter().find_map(|e| match e {
+                        PumpEvent::Trade(t) if t.mint == create_event.mint => Some(t.sol_amount),
+                        _ => None,
+                    });
+                    let dev_buy_sol = dev_buy.unwrap_or(0) as f64 / LAMPORTS_PER_SOL as f64;
+
+                    let entry = &config.entry;
+                    let passes = dev_buy_sol >= entry.min_dev_buy_sol
+                        && entry.max_dev_buy_sol.is_none_or(|max| dev_buy_sol <= max)
+                        && !entry
+                            .symbol_deny
+                            .iter()
+                            .any(|s| s.eq_ignore_ascii_case(&create_event.symbol));
+                    if !passes {
+                        continue;
+                    }
+
+                    candidates += 1;
+                    if rng.next_f64() >= config.fee_tier.land_probability {
+                        missed += 1;
+                        continue;
+                    }
+                    pending.insert(
+                        create_event.mint,
+                        PendingEntry {
+                            create_slot: message.slot,
+                            target_slot: message.slot + config.fee_tier.slot_delay,
+                        },
+                    );
+                }
+                PumpEvent::Trade(trade_event) => {
+                    snipers.on_trade(trade_event, message.slot, "");
+                    now_ts = now_ts.max(trade_event.get_timestamp());
+                    let curve = (
+                        trade_event.get_virtual_sol_reserves(),
+                        trade_event.get_virtual_token_reserves(),
+                    );
+                    curves.insert(trade_event.mint, curve);
+
+                    let reason = match open.get(&trade_event.mint) {
+                        Some(position) => {
+                            let value = curve_sell_sol(curve.0, curve.1, position.tokens) as f64;
+                            let cost = position.cost_lamports as f64;
+                            let exit = &config.exit;
+                            if exit.take_profit_pct.is_some_and(|tp| value >= cost * (1.0 + tp)) {
+                                Some("take_profit")
+                            } else if exit.stop_loss_pct.is_some_and(|sl| value <= cost * (1.0 - sl)) {
+                                Some("stop_loss")
+                            } else {
+                                None
+                            }
+                        }
+                        None => None,
+                    };
+                    if let Some(reason) = reason {
+                        let position = open.remove(&trade_event.mint).unwrap();
+                        trades.push(close(trade_event.mint, position, curve, now_ts, reason));
+                    }
+                }
+            }
+        }
+
+        let expired: Vec<Pubkey> = open
+            .iter()
+            .filter(|(_, position)| now_ts - position.entry_ts >= config.exit.hold_secs)
+            .map(|(mint, _)| *mint)
+            .collect();
+        for mint in expired {
+            let position = open.remove(&mint).unwrap();
+            let curve = curves[&mint];
+            trades.push(close(mint, position, curve, now_ts, "hold_expired"));
+        }
+    }
+
+    for (mint, position) in open.drain() {
+        let curve = curves[&mint];
+        trades.push(close(mint, position, curve, now_ts, "end_of_data"));
+    }
+
+    trades.sort_by_key(|t| (t.entry_slot, t.exit_ts));
+    let metrics = compute_metrics(&trades);
+
+    BacktestReport {
+        name: config.name.clone(),
+        candidates,
+        missed,
+        trades,
+        metrics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::config::{EntryFilter, ExitRules, FeeTier};
+    use crate::utils::layouts::{CreateEvent, TradeEvent};
+
+    const SIZE: u64 = 100_000_000;
+
+    fn config(land_probability: f64) -> BacktestConfig {
+        BacktestConfig {
+            name: "test".to_string(),
+            size_sol: SIZE as f64 / LAMPORTS_PER_SOL as f64,
+            entry: EntryFilter::default(),
+            fee_tier: FeeTier {
+                priority_fee_lamports: 10_000,
+                land_probability,
+                slot_delay: 2,
+            },
+            exit: ExitRules {
+                hold_secs: 10,
+                take_profit_pct: None,
+                stop_loss_pct: None,
+            },
+            seed: 42,
+        }
+    }
+
+    fn trade(mint: Pubkey, user: Pubkey, timestamp: i64, curve: (u64, u64)) -> PumpEvent {
+        PumpEvent::Trade(TradeEvent {
+            mint,
+            sol_amount: 1_000_000_000,
+            token_amount: 0,
+            is_buy: true,
+            user,
+            timestamp,
+            virtual_sol_reserves: curve.0,
+            virtual_token_reserves: curve.1,
+        })
+    }
+
+    // a create with the creator's buy, then a trade in each of the next three slots
+    fn launch() -> (Pubkey, Vec<RecordedMessage>) {
+        let (mint, creator, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let create = PumpEvent::Create(CreateEvent {
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            uri: String::new(),
+            mint,
+            bonding_curve: Pubkey::new_unique(),
+            user: creator,
+        });
+        let messages = vec![
+            RecordedMessage {
+                slot: 100,
+                events: vec![create, trade(mint, creator, 0, (31_000_000_000, 1_038_387_096_774_194))],
+            },
+            RecordedMessage {
+                slot: 101,
+                events: vec![trade(mint, other, 1, (32_000_000_000, 1_005_937_500_000_000))],
+            },
+            RecordedMessage {
+                slot: 102,
+                events: vec![trade(mint, other, 2, (33_000_000_000, 975_454_545_454_546))],
+            },
+            RecordedMessage {
+                slot: 103,
+                events: vec![trade(mint, other, 20, (40_000_000_000, 804_750_000_000_000))],
+            },
+        ];
+        (mint, messages)
+    }
+
+    #[test]
+    fn a_buy_lands_after_the_slot_delay_and_exits_when_the_hold_ends() {
+        let (mint, messages) = launch();
+        let report = run_backtest(&config(1.0), &messages);
+
+        assert_eq!((report.candidates, report.missed), (1, 0));
+        assert_eq!(report.trades.len(), 1);
+        let t = &report.trades[0];
+        assert_eq!(t.mint, mint);
+        assert_eq!((t.create_slot, t.entry_slot), (100, 102));
+        // priced against the curve as of slot 101, the last one before landing
+        assert_eq!(t.tokens, curve_buy_tokens(32_000_000_000, 1_005_937_500_000_000, SIZE));
+        assert_eq!(t.cost_lamports, SIZE + 1_000_000);
+        assert_eq!(t.proceeds_lamports, curve_sell_sol(40_000_000_000, 804_750_000_000_000, t.tokens));
+        assert_eq!(t.fees_lamports, 2 * (BASE_FEE_LAMPORTS + 10_000));
+        assert_eq!(
+            t.pnl_lamports,
+            t.proceeds_lamports as i64 - t.cost_lamports as i64 - t.fees_lamports as i64
+        );
+        assert_eq!((t.entry_ts, t.exit_ts, t.exit_reason), (1, 20, "hold_expired"));
+    }
+
+    #[test]
+    fn a_buy_that_does_not_land_is_missed() {
+        let (_, messages) = launch();
+        let report = run_backtest(&config(0.0), &messages);
+
+        assert_eq!((report.candidates, report.missed), (1, 1));
+        assert!(report.trades.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use super::engine::{BacktestReport, TradeResult};
+use crate::utils::constants::LAMPORTS_PER_SOL;
+
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    pub trades: usize,
+    pub wins: usize,
+    pub hit_rate: f64,
+    pub total_pnl_sol: f64,
+    pub avg_pnl_sol: f64,
+    pub max_drawdown_sol: f64,
+    /// Mean over standard deviation of per-trade returns, scaled by sqrt(trades).
+    pub sharpe: f64,
+}
+
+pub fn compute_metrics(trades: &[TradeResult]) -> Metrics {
+    if trades.is_empty() {
+        return Metrics::default();
+    }
+
+    let lamports = LAMPORTS_PER_SOL as f64;
+    let n = trades.len() as f64;
+    let wins = trades.iter().filter(|t| t.pnl_lamports > 0).count();
+    let total_pnl_sol = trades.iter().map(|t| t.pnl_lamports as f64).sum::<f64>() / lamports;
+
+    // the equity curve follows the order trades were closed in
+    let mut by_exit: Vec<&TradeResult> = trades.iter().collect();
+    by_exit.sort_by_key(|t| t.exit_ts);
+
+    let mut equity = 0.0;
+    let mut peak = 0.0;
+    let mut max_drawdown_sol: f64 = 0.0;
+    for trade in &by_exit {
+        equity += trade.pnl_lamports as f64 / lamports;
+        if equity > peak {
+            peak = equity;
+        }
+        max_drawdown_sol = max_drawdown_sol.max(peak - equity);
+    }
+
+    let returns: Vec<f64> = by_exit
+        .iter()
+        .map(|t| t.pnl_lamports as f64 / t.cost_lamports.max(1) as f64)
+        .collect();
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+    let sharpe = if variance > 0.0 {
+        mean / variance.sqrt() * n.sqrt()
+    } else {
+        0.0
+    };
+
+    Metrics {
+        trades: trades.len(),
+        wins,
+        hit_rate: wins as f64 / n,
+        total_pnl_sol,
+        avg_pnl_sol: total_pnl_sol / n,
+        max_drawdown_sol,
+        sharpe,
+    }
+}
+
+pub fn write_trades_csv(path: &Path, trades: &[TradeResult]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(
+        file,
+        "mint,create_slot,entry_slot,entry_ts,exit_ts,tokens,cost_lamports,proceeds_lamports,fees_lamports,pnl_lamports,exit_reason"
+    )?;
+    for t in trades {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            t.mint,
+            t.create_slot,
+            t.entry_slot,
+            t.entry_ts,
+            t.exit_ts,
+            t.tokens,
+            t.cost_lamports,
+            t.proceeds_lamports,
+            t.fees_lamports,
+            t.pnl_lamports,
+            t.exit_reason
+        )?;
+    }
+    Ok(())
+}
+
+pub fn print_summary(reports: &[BacktestReport]) {
+    println!(
+        "{:<20} {:>10} {:>8} {:>8} {:>6} {:>9} {:>12} {:>12} {:>12} {:>8}",
+        "config", "candidates", "missed", "trades", "wins", "hit_rate", "pnl_sol", "avg_pnl_sol", "max_dd_sol", "sharpe"
+    );
+    for report in reports {
+        let m = &report.metrics;
+        println!(
+            "{:<20} {:>10} {:>8} {:>8} {:>6} {:>9.3} {:>12.5} {:>12.5} {:>12.5} {:>8.3}",
+            report.name,
+            report.candidates,
+            report.missed,
+            m.trades,
+            m.wins,
+            m.hit_rate,
+            m.total_pnl_sol,
+            m.avg_pnl_sol,
+            m.max_drawdown_sol,
+            m.sharpe
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn trade(exit_ts: i64, pnl_sol: f64) -> TradeResult {
+        TradeResult {
+            mint: Pubkey::new_unique(),
+            create_slot: 0,
+            entry_slot: 0,
+            entry_ts: 0,
+            exit_ts,
+            tokens: 0,
+            cost_lamports: LAMPORTS_PER_SOL,
+            proceeds_lamports: 0,
+            fees_lamports: 0,
+            pnl_lamports: (pnl_sol * LAMPORTS_PER_SOL as f64) as i64,
+            exit_reason: "hold_expired",
+        }
+    }
+
+    #[test]
+    fn metrics_follow_the_exit_order() {
+        // closed as +0.5, -1.0, +0.25: equity 0.5, -0.5, -0.25
+        let trades = vec![trade(30, 0.25), trade(10, 0.5), trade(20, -1.0)];
+        let m = compute_metrics(&trades);
+
+        assert_eq!(m.trades, 3);
+        assert_eq!(m.wins, 2);
+        assert!((m.hit_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert!((m.total_pnl_sol + 0.25).abs() < 1e-9);
+        assert!((m.avg_pnl_sol + 0.25 / 3.0).abs() < 1e-9);
+        assert!((m.max_drawdown_sol - 1.0).abs() < 1e-9);
+        // mean -1/12 over a population std dev of sqrt(31/72), times sqrt(3)
+        assert!((m.sharpe + 0.219_970_672_5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_trades_no_metrics() {
+        let m = compute_metrics(&[]);
+        assert_eq!(m.trades, 0);
+        assert_eq!(m.sharpe, 0.0);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod config;
+pub mod dataset;
+pub mod engine;
+pub mod metrics;
@@ -1,33 +1,168 @@
+use chrono::Local;
+use clap::{Arg, Command};
 use dotenv::dotenv;
-use serde_json::Value;
+use std::collections::HashSet;
 use std::env;
+use std::path::PathBuf;
 use std::sync::Arc;
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::{compute_budget::ComputeBudgetInstruction, signature::Keypair, signer::Signer};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+use std::str::FromStr;
 use futures_util::stream::StreamExt;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::Message::Text;
 
 mod utils;
 use utils::check_logs_buy_sell::*;
+use utils::bus::EventBus;
+use utils::candles::{CandleConfig, CandleStore};
 use utils::constants::*;
+use utils::control::{self, ControlConfig};
+use utils::pipeline;
+use utils::positions::PositionBook;
+use utils::risk::{RiskConfig, RiskManager};
+use utils::recorder::Recorder;
+use utils::replay::replay_files;
+use utils::rug_watch::{RugConfig, RugWatch};
+use utils::shutdown::{self, ShutdownConfig};
+use utils::slots::SlotClock;
+use utils::holders::{HolderAnalyser, HolderConfig};
+use utils::insiders::{InsiderConfig, InsiderDetector};
+use utils::metadata::{MetadataConfig, MetadataFilter};
+use utils::snipers::{SniperBoard, SniperConfig};
 
 mod txn;
+use txn::executor::{Executor, SimExecutor};
+use txn::fees::{FeeConfig, FeeLadders, FeeOracle};
+use txn::tuner::{FeeTuner, TunerConfig};
+use txn::lookup_table::{create_table, extend_table, load_lookup_table, static_accounts};
+use txn::nonce::{create_nonce_accounts, NoncePool};
 use txn::spam_txn::*;
 
 mod sol_wss_methods;
 use sol_wss_methods::log_subscribe::*;
 
+mod strategy;
+use strategy::runner::StrategyRunner;
+
+mod wallet;
+use wallet::balance::BalanceConfig;
+use wallet::funding::{fund_wallets, list_wallets, sweep_wallets};
+use wallet::keys::{encrypt_keypair, forget_password, load_keypair, load_payer, read_password};
+use wallet::pool::WalletPool;
+use wallet::reclaim::reclaim_wallet;
+
+mod backtest;
+use backtest::config::load_configs;
+use backtest::dataset::load_messages;
+use backtest::engine::run_backtest;
+use backtest::metrics::{print_summary, write_trades_csv};
+
 #[tokio::main]
 pub async fn main() {
     dotenv().ok();
     env_logger::init();
 
-    let empty_vec: Vec<Value> = vec![];
+    let matches = Command::new("sniper")
+        .subcommand(
+            Command::new("replay")
+                .about("Feed recorded notifications through the bot with a simulated executor")
+                .arg(Arg::new("files").required(true).num_args(1..))
+                .arg(Arg::new("speed").long("speed").help("1 = real time, 0 = as fast as possible"))
+                .arg(Arg::new("out").long("out").help("Write simulated fills as JSON lines")),
+        )
+        .subcommand(
+            Command::new("backtest")
+                .about("Run strategy configs over recorded pump.fun trade streams")
+                .arg(Arg::new("files").required(true).num_args(1..))
+                .arg(Arg::new("config").long("config").required(true))
+                .arg(Arg::new("out").long("out").help("Directory for per-config trade CSVs")),
+        )
+        .subcommand(
+            Command::new("keys")
+                .about("Encrypt keypair files into keystores")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("encrypt")
+                        .about("Write a password-encrypted keystore for a keypair file")
+                        .arg(Arg::new("keypair").required(true))
+                        .arg(Arg::new("out").long("out").required(true)),
+                )
+                .subcommand(
+                    Command::new("pubkey")
+                        .about("Print the public key of a keypair file or keystore")
+                        .arg(Arg::new("file").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("lut")
+                .about("Manage the address lookup table used for v0 transactions, PAYER is its authority")
+                .subcommand_required(true)
+                .subcommand(Command::new("create").about("Create a table holding the static pump.fun accounts"))
+                .subcommand(
+                    Command::new("extend")
+                        .about("Add the static accounts and any extra addresses missing from a table")
+                        .arg(Arg::new("table").required(true))
+                        .arg(Arg::new("addresses").num_args(0..)),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("List the addresses in a table")
+                        .arg(Arg::new("table").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("nonce")
+                .about("Create durable nonce accounts for every trading wallet, funded by PAYER")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Create --count nonce accounts per wallet")
+                        .arg(Arg::new("count").long("count").default_value("2")),
+                ),
+        )
+        .subcommand(
+            Command::new("reclaim")
+                .about("Close empty token accounts of every trading wallet to recover rent")
+                .arg(Arg::new("dust").long("dust").help("Sell and close balances worth less than this many SOL"))
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Only report what would be closed"),
+                ),
+        )
+        .subcommand(
+            Command::new("wallets")
+                .about("Manage the trading wallet pool, PAYER acts as the treasury")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("Show treasury and wallet balances"))
+                .subcommand(
+                    Command::new("fund")
+                        .about("Top every wallet up to --amount SOL from the treasury")
+                        .arg(Arg::new("amount").long("amount").required(true)),
+                )
+                .subcommand(
+                    Command::new("sweep")
+                        .about("Send everything above --keep SOL back to the treasury")
+                        .arg(Arg::new("keep").long("keep")),
+                ),
+        )
+        .get_matches();
+
+    // Backtests are offline, they need neither a wallet nor an RPC
+    if let Some(sub) = matches.subcommand_matches("backtest") {
+        run_backtest_command(sub);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("keys") {
+        run_keys_command(sub);
+        return;
+    }
 
     // Load environment variables
-    let payer_key = env::var("PAYER").expect("payer must be set in .env file");
-    let _payer = Arc::new(Keypair::from_base58_string(&payer_key));
+    let payer = Arc::new(load_payer());
 
     let rpc_https_url = env::var("RPC_HTTPS_URL").expect("RPC_HTTPS_URL must be set in .env file");
     let wss_https_url = env::var("WSS_HTTPS_URL").expect("WSS_HTTPS_URL must be set in .env file");
@@ -52,85 +187,558 @@ pub async fn main() {
         .map(|v| v.parse::<f64>().expect("slippage must be a valid f64"))
         .unwrap_or(0.0);
 
+    // Emergency exit uses its own, more aggressive fee ladder
+    let rug_spam_limit = env::var("rug_spam_limit")
+        .map(|v| v.parse::<u64>().expect("rug_spam_limit must be a valid u64"))
+        .unwrap_or(spam_limit);
+
+    let rug_budget_price = env::var("rug_budget_price")
+        .map(|v| v.parse::<u64>().expect("rug_budget_price must be a valid u64"))
+        .unwrap_or(budget_price * 10);
+
     // Prepare constants
     let prices_4_spam = array_of_fees(spam_limit, budget_price).await;
+    let rug_prices_4_spam = array_of_fees(rug_spam_limit, rug_budget_price).await;
+    let rug_watch = Arc::new(RugWatch::new(RugConfig::from_env()));
+    let candle_store = Arc::new(CandleStore::new(CandleConfig::from_env()));
+
+    {
+        let candle_store = candle_store.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                if let Err(e) = candle_store.flush() {
+                    eprintln!("Failed to flush candles: {:?}", e);
+                }
+            }
+        });
+    }
     let client = Arc::new(RpcClient::new(rpc_https_url.to_string()));
-    let m_pk = _payer.as_ref().pubkey();
 
     let investment_lamported = investment * LAMPORTS_PER_SOL as f64;
-    let adjusted_investment_for_fees = investment_lamported + (investment_lamported * 0.03);
 
     // Compute budget instruction
     let unit_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(budget_limit);
 
-    // WebSocket loop
-    loop {
-        match connect_async(wss_https_url.to_string()).await {
-            Ok((mut stream, _)) => {
-                println!("WebSocket is open");
+    let mut wallets = WalletPool::from_env(payer.clone());
+    forget_password();
+
+    if let Some(sub) = matches.subcommand_matches("wallets") {
+        run_wallets_command(sub, &client, &payer, &wallets).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("lut") {
+        run_lut_command(sub, &client, &payer).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("nonce") {
+        run_nonce_command(sub, &client, &payer, &wallets).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("reclaim") {
+        run_reclaim_command(sub, &client, &wallets).await;
+        return;
+    }
+
+    let replay_speed = matches.subcommand_matches("replay").map(|sub| {
+        sub.get_one::<String>("speed")
+            .map(|v| v.parse::<f64>().expect("speed must be a valid f64"))
+            .unwrap_or(1.0)
+    });
+
+    // Live trading checks every buy against the wallet's balance first
+    if replay_speed.is_none() {
+        wallets
+            .follow_balances(BalanceConfig::from_env(), client.clone(), &wss_https_url)
+            .await;
+    }
+
+    // v0 transactions through a lookup table, replay has no use for one
+    let lookup_table = match (env::var("lookup_table"), replay_speed) {
+        (Ok(address), None) => {
+            let address = Pubkey::from_str(&address).expect("lookup_table must be a valid pubkey");
+            Some(
+                load_lookup_table(&client, &address)
+                    .await
+                    .unwrap_or_else(|e| panic!("Failed to load lookup table {}: {}", address, e)),
+            )
+        }
+        _ => None,
+    };
+
+    // Durable nonce mode, live only
+    let nonces = match (env::var("nonce_accounts"), replay_speed) {
+        (Ok(accounts), None) => {
+            let accounts = accounts
+                .split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(|a| Pubkey::from_str(a).expect("nonce_accounts must be valid pubkeys"))
+                .collect();
+            let nonces = Arc::new(NoncePool::load(&client, accounts).await);
+            println!("Loaded {} nonce accounts", nonces.len());
+            Some(nonces)
+        }
+        _ => None,
+    };
+
+    let fee_config = FeeConfig::from_env();
+    let tuner_config = TunerConfig::from_env();
+    // both would price the buy ladder, and only one of them can
+    assert!(
+        fee_config.is_none() || tuner_config.is_none(),
+        "fee_tuning and fee_percentiles can't be combined, pick one to price the buy ladder"
+    );
+
+    // Fee ladders priced from recent prioritization fees, live only
+    let fee_oracle = match (fee_config, replay_speed) {
+        (Some(config), None) => {
+            let oracle = FeeOracle::spawn(client.clone(), config.window_slots, config.sample_interval);
+            Some((oracle, config))
+        }
+        _ => None,
+    };
+    // Landing-rate tuning of the buy ladder, live only
+    let fee_tuner = match (tuner_config, replay_speed) {
+        (Some(config), None) => {
+            let static_ladder: Vec<u64> = (0..spam_limit).map(|i| budget_price + i).collect();
+            Some(FeeTuner::load(config, budget_limit, &static_ladder))
+        }
+        _ => None,
+    };
+    let fees = FeeLadders::new(budget_limit, prices_4_spam, rug_prices_4_spam, fee_oracle, fee_tuner);
+
+    // Live follows slotSubscribe, replay only the recorded notification slots
+    let slots = match replay_speed {
+        Some(_) => Arc::new(SlotClock::new()),
+        None => SlotClock::spawn(wss_https_url.to_string()),
+    };
 
-                if let Err(e) = send_request(&mut stream).await {
-                    eprintln!("Failed to send request: {:?}", e);
-                    continue;
+    let executor = match replay_speed {
+        Some(_) => Executor::Simulated(SimExecutor::new()),
+        None => {
+            let sender = txn::sender::from_env(&client, &payer, &slots);
+            println!("Sending through {}", sender.name());
+            Executor::Live(sender)
+        }
+    };
+
+    let snipers = Arc::new(SniperBoard::new(SniperConfig::from_env()));
+    // early buy fees are read on a client of their own, never the trading one
+    if replay_speed.is_none() {
+        snipers.spawn_fee_lookups(RpcClient::new(rpc_https_url.to_string()));
+    }
+    let insiders = Arc::new(InsiderDetector::new(InsiderConfig::from_env()));
+    let metadata = Arc::new(MetadataFilter::new(MetadataConfig::from_env()));
+    let holders = Arc::new(HolderAnalyser::new(HolderConfig::from_env()));
+    let bus_capacity = env::var("bus_capacity")
+        .map(|v| v.parse::<usize>().expect("bus_capacity must be a valid usize"))
+        .unwrap_or(4_096);
+
+    let ctx = Arc::new(TradeContext {
+        client,
+        wallets,
+        slippage,
+        unit_limit_ix,
+        fees,
+        lookup_table,
+        nonces,
+        rug_watch,
+        candle_store,
+        strategies: StrategyRunner::from_env(investment_lamported),
+        positions: PositionBook::new(),
+        risk: RiskManager::new(RiskConfig::from_env()),
+        executor,
+        slots,
+        snipers,
+        insiders,
+        metadata,
+        holders,
+        bus: EventBus::new(bus_capacity),
+    });
+    let metrics = pipeline::spawn(&ctx);
+
+    if let (Some(sub), Some(speed)) = (matches.subcommand_matches("replay"), replay_speed) {
+        let files: Vec<PathBuf> = sub
+            .get_many::<String>("files")
+            .expect("replay needs at least one file")
+            .map(PathBuf::from)
+            .collect();
+
+        match replay_files(&files, speed, ctx.clone()).await {
+            Ok(replayed) => println!("Replayed {} messages", replayed),
+            Err(e) => eprintln!("Replay failed: {:?}", e),
+        }
+
+        if let Some(sim) = ctx.executor.as_simulated() {
+            println!("Simulated fills: {}", sim.fills().len());
+            if let Some(out) = sub.get_one::<String>("out") {
+                if let Err(e) = sim.write_fills(&PathBuf::from(out)) {
+                    eprintln!("Failed to write fills: {:?}", e);
                 }
+            }
+        }
+        println!("Events: {}", metrics.summary());
+        let _ = ctx.candle_store.flush();
+        let _ = ctx.snipers.flush();
+        return;
+    }
 
-                while let Some(message) = stream.next().await {
-                    match message {
-                        Ok(Text(text)) => {
-                            match serde_json::from_str::<Value>(&text) {
-                                Ok(parsed) => {
-                                    // Extract logs
-                                    let jsonified = parsed["params"]["result"]["value"]["logs"]
-                                        .as_array()
-                                        .unwrap_or(&empty_vec);
-
-                                    let logs: Vec<String> = jsonified
-                                        .iter()
-                                        .map(|v| v.as_str().unwrap_or_default().to_string())
-                                        .collect();
-
-                                    let client_clone = client.clone();
-                                    let payer_clone = _payer.clone();
-                                    let unit_limit_ix_clone = unit_limit_ix.clone();
-                                    let prices_4_spam_clone = prices_4_spam.clone();
-
-                                    tokio::spawn(async move {
-                                        process_logs(
-                                            &logs,
-                                            client_clone,
-                                            payer_clone,
-                                            investment_lamported,
-                                            slippage,
-                                            adjusted_investment_for_fees,
-                                            unit_limit_ix_clone,
-                                            prices_4_spam_clone,
-                                            &m_pk,
-                                        )
-                                        .await;
-                                    });
-                                    
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to parse JSON: {:?}", e);
+    let recorder = env::var("record_dir").ok().map(|dir| Recorder::spawn(PathBuf::from(dir)));
+
+    // Positions a previous run persisted on shutdown
+    let shutdown_config = ShutdownConfig::from_env();
+    let restored = shutdown::restore(&ctx, &shutdown_config.positions_file).await;
+    if restored > 0 {
+        println!("Restored {} open positions from the last run", restored);
+    }
+
+    // Local HTTP control API
+    if let Some(config) = ControlConfig::from_env() {
+        control::spawn(config, ctx.clone(), metrics.clone());
+    }
+
+    // Strategy timers (holds, trailing exits, ...)
+    let timers = {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                run_timers(&ctx);
+            }
+        })
+    };
+
+    // Keep nonce values current and cancel ladders that never landed
+    if let Some(nonces) = ctx.nonces.clone() {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+                nonces
+                    .refresh(&ctx.client, |authority| {
+                        ctx.wallets
+                            .wallets()
+                            .iter()
+                            .find(|w| w.pubkey == *authority)
+                            .map(|w| w.keypair.as_ref())
+                    })
+                    .await;
+            }
+        });
+    }
+
+    // What went through the pipeline
+    {
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                println!(
+                    "{}:: events: {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    metrics.summary()
+                );
+            }
+        });
+    }
+
+    // Recurring early buyers, and the raw early buys to disk
+    {
+        let snipers = ctx.snipers.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                if let Err(e) = snipers.flush() {
+                    eprintln!("Failed to flush early buys: {:?}", e);
+                }
+                for (wallet, stats) in snipers.leaderboard(10) {
+                    println!(
+                        "{}:: sniper {}: {} launches, {} in block 0, {} lamports, avg fee {:?}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        wallet,
+                        stats.launches,
+                        stats.block0,
+                        stats.total_sol,
+                        stats.avg_priority_fee()
+                    );
+                }
+            }
+        });
+    }
+
+    // Per-wallet summary when trading from a pool
+    if ctx.wallets.len() > 1 {
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                for status in ctx.wallets.status(&ctx.positions) {
+                    println!(
+                        "{}:: wallet {}: {} open, {} lamports exposed, balance {:?}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        status.pubkey,
+                        status.open_positions,
+                        status.exposure_lamports,
+                        status.lamports
+                    );
+                }
+            }
+        });
+    }
+
+    // WebSocket loop, until SIGINT/SIGTERM
+    let ingest = async {
+        loop {
+            match connect_async(wss_https_url.to_string()).await {
+                Ok((mut stream, _)) => {
+                    println!("WebSocket is open");
+
+                    if let Err(e) = send_request(&mut stream).await {
+                        eprintln!("Failed to send request: {:?}", e);
+                        continue;
+                    }
+
+                    while let Some(message) = stream.next().await {
+                        match message {
+                            Ok(Text(text)) => {
+                                if let Some(recorder) = &recorder {
+                                    recorder.record(&text);
                                 }
+                                handle_message(&text, &ctx);
+                            }
+                            Ok(_) => {
+                                println!("Received non-text message");
+                            }
+                            Err(e) => {
+                                eprintln!("WebSocket message error: {:?}", e);
+                                break;
                             }
-                        }
-                        Ok(_) => {
-                            println!("Received non-text message");
-                        }
-                        Err(e) => {
-                            eprintln!("WebSocket message error: {:?}", e);
-                            break;
                         }
                     }
                 }
+                Err(e) => {
+                    eprintln!("WebSocket connection error: {:?}", e);
+                }
+            }
+
+            println!("WebSocket is closed. Reconnecting in 5 seconds...");
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    };
+    tokio::select! {
+        _ = ingest => {}
+        name = shutdown::signal() => println!(
+            "{}:: {} received, shutting down",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            name
+        ),
+    }
+
+    // a hold expiring now would race the flatten or the saved positions
+    timers.abort();
+    // the first signal is spent, a second one stops waiting on sends
+    tokio::select! {
+        _ = shutdown::run(&ctx, &shutdown_config) => {}
+        name = shutdown::signal() => {
+            println!(
+                "{}:: {} received again, exiting now",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                name
+            );
+            shutdown::save_open(&ctx, &shutdown_config);
+            std::process::exit(130);
+        }
+    }
+    if let Some(recorder) = recorder {
+        recorder.close().await;
+    }
+    if let Err(e) = ctx.candle_store.flush() {
+        eprintln!("Failed to flush candles: {:?}", e);
+    }
+    if let Err(e) = ctx.snipers.flush() {
+        eprintln!("Failed to flush early buys: {:?}", e);
+    }
+    println!("{}:: events: {}", Local::now().format("%Y-%m-%d %H:%M:%S"), metrics.summary());
+}
+
+fn run_backtest_command(sub: &clap::ArgMatches) {
+    let files: Vec<PathBuf> = sub
+        .get_many::<String>("files")
+        .expect("backtest needs at least one file")
+        .map(PathBuf::from)
+        .collect();
+    let config_path = PathBuf::from(sub.get_one::<String>("config").expect("config is required"));
+
+    let configs = load_configs(&config_path).expect("Failed to load backtest config");
+    let messages = load_messages(&files).expect("Failed to load recordings");
+    println!("Loaded {} messages with pump.fun events", messages.len());
+
+    let reports: Vec<_> = configs
+        .iter()
+        .map(|config| run_backtest(config, &messages))
+        .collect();
+
+    if let Some(out) = sub.get_one::<String>("out") {
+        let out = PathBuf::from(out);
+        std::fs::create_dir_all(&out).expect("Failed to create output directory");
+        for report in &reports {
+            let path = out.join(format!("{}-trades.csv", report.name));
+            if let Err(e) = write_trades_csv(&path, &report.trades) {
+                eprintln!("Failed to write {:?}: {:?}", path, e);
+            }
+        }
+    }
+
+    print_summary(&reports);
+}
+
+async fn run_wallets_command(sub: &clap::ArgMatches, client: &RpcClient, treasury: &Keypair, pool: &WalletPool) {
+    let sol_arg = |matches: &clap::ArgMatches, name: &str| {
+        matches
+            .get_one::<String>(name)
+            .map(|v| v.parse::<f64>().unwrap_or_else(|_| panic!("{} must be a valid f64", name)))
+            .map(|sol| (sol * LAMPORTS_PER_SOL as f64) as u64)
+    };
+
+    match sub.subcommand() {
+        Some(("list", _)) => list_wallets(client, treasury, pool).await,
+        Some(("fund", args)) => {
+            let target = sol_arg(args, "amount").expect("amount is required");
+            fund_wallets(client, treasury, pool, target).await;
+        }
+        Some(("sweep", args)) => {
+            let keep = sol_arg(args, "keep").unwrap_or(0);
+            sweep_wallets(client, treasury, pool, keep).await;
+        }
+        _ => {}
+    }
+}
+
+fn run_keys_command(sub: &clap::ArgMatches) {
+    match sub.subcommand() {
+        Some(("encrypt", args)) => {
+            let keypair_path = PathBuf::from(args.get_one::<String>("keypair").expect("keypair is required"));
+            let out = PathBuf::from(args.get_one::<String>("out").expect("out is required"));
+
+            let keypair = load_keypair(&keypair_path).expect("Failed to read keypair");
+            let password = read_password("New keystore password: ").expect("Failed to read password");
+            if env::var("KEYSTORE_PASSWORD_FD").is_err() {
+                let confirm = read_password("Repeat password: ").expect("Failed to read password");
+                if *confirm != *password {
+                    eprintln!("Passwords do not match");
+                    return;
+                }
+            }
+
+            let keystore = encrypt_keypair(&keypair, &password);
+            let json = serde_json::to_string_pretty(&keystore).expect("Failed to serialize keystore");
+            std::fs::write(&out, json).expect("Failed to write keystore");
+            println!("Wrote keystore for {} to {:?}", keystore.pubkey, out);
+        }
+        Some(("pubkey", args)) => {
+            let path = PathBuf::from(args.get_one::<String>("file").expect("file is required"));
+            match load_keypair(&path) {
+                Ok(keypair) => println!("{}", keypair.pubkey()),
+                Err(e) => eprintln!("Failed to load {:?}: {}", path, e),
             }
+        }
+        _ => {}
+    }
+}
+
+async fn run_reclaim_command(sub: &clap::ArgMatches, client: &RpcClient, pool: &WalletPool) {
+    let dust_lamports = sub
+        .get_one::<String>("dust")
+        .map(|v| v.parse::<f64>().expect("dust must be a valid f64"))
+        .map(|sol| (sol * LAMPORTS_PER_SOL as f64) as u64)
+        .unwrap_or(0);
+    let dry_run = sub.get_flag("dry-run");
+
+    // positions persisted on shutdown are picked up again on the next start
+    let keep = if dust_lamports > 0 {
+        let positions_file = ShutdownConfig::from_env().positions_file;
+        match shutdown::saved_mints(&positions_file) {
+            Ok(keep) => keep,
             Err(e) => {
-                eprintln!("WebSocket connection error: {:?}", e);
+                eprintln!(
+                    "Refusing --dust, can't read saved positions in {}: {}",
+                    positions_file.display(),
+                    e
+                );
+                return;
+            }
+        }
+    } else {
+        HashSet::new()
+    };
+
+    for wallet in pool.wallets() {
+        let report = reclaim_wallet(client, &wallet.keypair, dust_lamports, &keep, dry_run).await;
+        println!(
+            "{}: {} accounts to close ({} dust sells, {} skipped) in {} txs, ~{:.4} SOL",
+            wallet.pubkey,
+            report.closed,
+            report.dust_sold,
+            report.skipped,
+            report.transactions,
+            report.reclaimed_lamports as f64 / LAMPORTS_PER_SOL as f64
+        );
+    }
+}
+
+async fn run_lut_command(sub: &clap::ArgMatches, client: &RpcClient, authority: &Keypair) {
+    let table_arg = |args: &clap::ArgMatches| {
+        let table = args.get_one::<String>("table").expect("table is required");
+        Pubkey::from_str(table).expect("table must be a valid pubkey")
+    };
+
+    match sub.subcommand() {
+        Some(("create", _)) => match create_table(client, authority).await {
+            Ok(address) => println!("Lookup table ready, set lookup_table={}", address),
+            Err(e) => eprintln!("Failed to create lookup table: {}", e),
+        },
+        Some(("extend", args)) => {
+            let mut addresses = static_accounts();
+            if let Some(extra) = args.get_many::<String>("addresses") {
+                addresses.extend(extra.map(|a| Pubkey::from_str(a).expect("addresses must be valid pubkeys")));
+            }
+            match extend_table(client, authority, &table_arg(args), addresses).await {
+                Ok(added) => println!("Added {} addresses", added),
+                Err(e) => eprintln!("Failed to extend lookup table: {}", e),
+            }
+        }
+        Some(("show", args)) => match load_lookup_table(client, &table_arg(args)).await {
+            Ok(table) => {
+                for address in table.addresses {
+                    println!("{}", address);
+                }
+            }
+            Err(e) => eprintln!("Failed to load lookup table: {}", e),
+        },
+        _ => {}
+    }
+}
+
+async fn run_nonce_command(sub: &clap::ArgMatches, client: &RpcClient, funder: &Keypair, pool: &WalletPool) {
+    if let Some(("create", args)) = sub.subcommand() {
+        let count = args
+            .get_one::<String>("count")
+            .map(|v| v.parse::<usize>().expect("count must be a valid usize"))
+            .unwrap_or(2);
+
+        let mut created = Vec::new();
+        for wallet in pool.wallets() {
+            match create_nonce_accounts(client, funder, &wallet.pubkey, count).await {
+                Ok(accounts) => created.extend(accounts),
+                Err(e) => eprintln!("Failed to create nonce accounts for {}: {}", wallet.pubkey, e),
             }
         }
 
-        println!("WebSocket is closed. Reconnecting in 5 seconds...");
-        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        let list: Vec<String> = created.iter().map(|a| a.to_string()).collect();
+        println!("nonce_accounts={}", list.join(","));
     }
 }
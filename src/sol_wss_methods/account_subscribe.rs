@@ -0,0 +1,29 @@
+use futures_util::SinkExt;
+use solana_sdk::pubkey::Pubkey;
+use tokio_tungstenite::tungstenite::Message::Text;
+use tokio_tungstenite::WebSocketStream;
+
+pub async fn send_account_subscribe(
+    stream: &mut WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    account: &Pubkey,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let request = format!(
+        r#"
+    {{
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "accountSubscribe",
+        "params": [
+                        "{}",
+                        {{
+                        "encoding": "base64",
+                        "commitment": "processed"
+                        }}
+                ]
+    }}
+    "#,
+        account
+    );
+
+    stream.send(Text(request)).await
+}
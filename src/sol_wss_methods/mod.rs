@@ -1 +1,3 @@
+pub mod account_subscribe;
 pub mod log_subscribe;
+pub mod slot_subscribe;
@@ -0,0 +1,17 @@
+use futures_util::SinkExt;
+use tokio_tungstenite::tungstenite::Message::Text;
+use tokio_tungstenite::WebSocketStream;
+
+pub async fn send_slot_subscribe(
+    stream: &mut WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let request = r#"
+    {
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "slotSubscribe"
+    }
+    "#;
+
+    stream.send(Text(request.to_string())).await
+}
@@ -0,0 +1,129 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+
+use super::{Fill, Intent, Side, Strategy};
+use crate::utils::candles::CandleStore;
+use crate::utils::layouts::TradeEvent;
+
+// a failed exit is tried again this long after it was reported
+const SELL_RETRY_MS: i64 = 2_000;
+
+/// Follows the wallets on its copy list: buys a mint when one of them buys
+/// it, and sells everything as soon as that wallet sells any of it. A buy not
+/// yet sent is cancelled instead. Only one copy per mint is held at a time.
+/// Wallets taken off the list are still followed out of the mints copied
+/// from them.
+pub struct CopyTrade {
+    name: String,
+    investment_lamports: u64,
+    wallets: HashSet<Pubkey>,
+    // mint -> wallet it was copied from, until the buy is dropped or the sell fills
+    copied: HashMap<Pubkey, Pubkey>,
+    held: HashSet<Pubkey>,
+    // mints whose sell is out and not filled or failed yet
+    selling: HashSet<Pubkey>,
+    // mint -> time a failed sell is tried again
+    retry_at: HashMap<Pubkey, i64>,
+}
+
+impl CopyTrade {
+    pub fn new(name: String, investment_lamports: u64, wallets: HashSet<Pubkey>) -> Self {
+        CopyTrade {
+            name,
+            investment_lamports,
+            wallets,
+            copied: HashMap::new(),
+            held: HashSet::new(),
+            selling: HashSet::new(),
+            retry_at: HashMap::new(),
+        }
+    }
+
+    fn sell(&mut self, mint: Pubkey) -> Vec<Intent> {
+        self.retry_at.remove(&mint);
+        self.selling.insert(mint);
+        vec![Intent::Sell { mint, pct: 1.0 }]
+    }
+}
+
+impl Strategy for CopyTrade {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_trade(&mut self, trade: &TradeEvent, _candles: &CandleStore) -> Vec<Intent> {
+        if trade.is_buy_bool() {
+            if !self.wallets.contains(&trade.user) || self.copied.contains_key(&trade.mint) {
+                return Vec::new();
+            }
+            self.copied.insert(trade.mint, trade.user);
+            return vec![Intent::Buy {
+                mint: trade.mint,
+                lamports: self.investment_lamports,
+            }];
+        }
+
+        if self.copied.get(&trade.mint) != Some(&trade.user) {
+            return Vec::new();
+        }
+        if !self.held.contains(&trade.mint) {
+            // a buy that already went out is sold once it fills
+            self.copied.remove(&trade.mint);
+            return vec![Intent::Cancel { mint: trade.mint }];
+        }
+        if self.selling.contains(&trade.mint) {
+            return Vec::new();
+        }
+        self.sell(trade.mint)
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        match fill.side {
+            Side::Buy => {
+                self.held.insert(fill.mint);
+                // the copied wallet sold while the buy was in flight
+                if !self.copied.contains_key(&fill.mint) && !self.selling.contains(&fill.mint) {
+                    return self.sell(fill.mint);
+                }
+            }
+            Side::Sell => {
+                self.copied.remove(&fill.mint);
+                self.held.remove(&fill.mint);
+                self.selling.remove(&fill.mint);
+                self.retry_at.remove(&fill.mint);
+            }
+        }
+        Vec::new()
+    }
+
+    fn on_sell_failed(&mut self, mint: &Pubkey, now_ms: i64) -> Vec<Intent> {
+        if self.selling.remove(mint) {
+            self.retry_at.insert(*mint, now_ms + SELL_RETRY_MS);
+        }
+        Vec::new()
+    }
+
+    fn on_timer(&mut self, now_ms: i64, _candles: &CandleStore) -> Vec<Intent> {
+        let due: Vec<Pubkey> = self
+            .retry_at
+            .iter()
+            .filter(|(_, retry_at)| now_ms >= **retry_at)
+            .map(|(mint, _)| *mint)
+            .collect();
+
+        due.into_iter().flat_map(|mint| self.sell(mint)).collect()
+    }
+
+    fn investment(&self) -> Option<u64> {
+        Some(self.investment_lamports)
+    }
+
+    fn set_investment(&mut self, lamports: u64) -> bool {
+        self.investment_lamports = lamports;
+        true
+    }
+
+    fn copy_list(&mut self) -> Option<&mut HashSet<Pubkey>> {
+        Some(&mut self.wallets)
+    }
+}
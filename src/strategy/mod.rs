@@ -0,0 +1,112 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+
+use crate::utils::candles::CandleStore;
+use crate::utils::holders::HolderStats;
+use crate::utils::layouts::TradeEvent;
+
+pub mod copy_trade;
+pub mod runner;
+pub mod snipe_and_hold;
+
+/// What a strategy wants done. Intents are checked against the strategy's
+/// budget by the runner before anything is sent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Intent {
+    Buy { mint: Pubkey, lamports: u64 },
+    /// `pct` of the held position, 1.0 sells everything.
+    Sell { mint: Pubkey, pct: f64 },
+    /// Drops a buy that has been decided but not yet sent.
+    Cancel { mint: Pubkey },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// A freshly created token, with the curve state after the creator's own buy.
+#[derive(Clone, Debug)]
+pub struct CreateInfo {
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    /// Slot of the notification the create arrived in, 0 if unknown.
+    pub slot: u64,
+    pub name: String,
+    pub symbol: String,
+    /// Where the metadata JSON lives, usually IPFS.
+    pub uri: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Fill {
+    pub mint: Pubkey,
+    pub side: Side,
+    pub token_amount: u64,
+    pub lamports: u64,
+    pub time_ms: i64,
+    /// Slot the transaction landed in, or the newest known slot when that was not read back.
+    pub slot: u64,
+}
+
+/// Entry/exit logic. Every hook is synchronous and returns the intents it wants
+/// executed; the default implementations do nothing.
+pub trait Strategy: Send {
+    fn name(&self) -> &str;
+
+    fn on_create(&mut self, _create: &CreateInfo) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// `candles` already include this trade.
+    fn on_trade(&mut self, _trade: &TradeEvent, _candles: &CandleStore) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    fn on_curve_update(
+        &mut self,
+        _mint: &Pubkey,
+        _virtual_sol_reserves: u64,
+        _virtual_token_reserves: u64,
+    ) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    fn on_fill(&mut self, _fill: &Fill) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// A sell of `mint` was not accepted, did not land or could not be
+    /// confirmed. The position is still held.
+    fn on_sell_failed(&mut self, _mint: &Pubkey, _now_ms: i64) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// A held mint's holder distribution was refreshed.
+    fn on_holders(&mut self, _mint: &Pubkey, _stats: &HolderStats) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    fn on_timer(&mut self, _now_ms: i64, _candles: &CandleStore) -> Vec<Intent> {
+        Vec::new()
+    }
+
+    /// Lamports spent per entry, for strategies that buy a fixed size.
+    fn investment(&self) -> Option<u64> {
+        None
+    }
+
+    /// Changes the entry size. `false` if the strategy has none to change.
+    fn set_investment(&mut self, _lamports: u64) -> bool {
+        false
+    }
+
+    /// The wallets whose trades the strategy copies, `None` if it copies none.
+    fn copy_list(&mut self) -> Option<&mut HashSet<Pubkey>> {
+        None
+    }
+}
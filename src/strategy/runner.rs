@@ -0,0 +1,295 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::env;
+use std::sync::Mutex;
+
+use super::copy_trade::CopyTrade;
+use super::snipe_and_hold::SnipeAndHold;
+use super::{CreateInfo, Fill, Intent, Strategy};
+use crate::utils::candles::CandleStore;
+use crate::utils::constants::LAMPORTS_PER_SOL;
+use crate::utils::holders::HolderStats;
+use crate::utils::layouts::TradeEvent;
+
+/// Entry conditions any strategy can be given, checked right before a buy is sent.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EntryLimits {
+    /// Slots after the create beyond which a buy is no longer sent.
+    pub max_slot_lag: Option<u64>,
+    /// Most other wallets that may have bought in the create slot.
+    pub max_block0_snipers: Option<usize>,
+    /// Most of the supply, in percent, the creator and wallets tied to them
+    /// may hold after the create slot. Needs `insider_detection`.
+    pub max_insider_pct: Option<f64>,
+    /// Bounds on the holder distribution when the buy is sent. Each one
+    /// costs an RPC call before the buy.
+    pub min_holders: Option<usize>,
+    pub max_top10_pct: Option<f64>,
+    pub max_creator_pct: Option<f64>,
+}
+
+impl EntryLimits {
+    pub fn needs_holders(&self) -> bool {
+        self.min_holders.is_some() || self.max_top10_pct.is_some() || self.max_creator_pct.is_some()
+    }
+}
+
+struct StrategySlot {
+    strategy: Mutex<Box<dyn Strategy>>,
+    budget_lamports: u64,
+    limits: Mutex<EntryLimits>,
+    committed_lamports: Mutex<u64>,
+}
+
+/// Runs several strategies side by side, each against its own SOL budget.
+/// Strategies are addressed by their index in the runner.
+pub struct StrategyRunner {
+    slots: Vec<StrategySlot>,
+    // buys that passed the budget check but were not sent yet, with their size
+    pending: Mutex<HashMap<(usize, Pubkey), u64>>,
+}
+
+impl StrategyRunner {
+    pub fn new(strategies: Vec<(Box<dyn Strategy>, u64, EntryLimits)>) -> Self {
+        let slots = strategies
+            .into_iter()
+            .map(|(strategy, budget_lamports, limits)| StrategySlot {
+                strategy: Mutex::new(strategy),
+                budget_lamports,
+                limits: Mutex::new(limits),
+                committed_lamports: Mutex::new(0),
+            })
+            .collect();
+
+        StrategyRunner {
+            slots,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Builds the runner from `strategies`, a `;` separated list of
+    /// `name[:key=value,...][@budget_sol]`. Defaults to a single
+    /// `snipe_and_hold` with the global investment and no budget cap.
+    /// `max_slot_lag`, `max_block0_snipers`, `max_insider_pct`, `min_holders`,
+    /// `max_top10_pct` and `max_creator_pct` are understood by every strategy.
+    pub fn from_env(investment_lamported: f64) -> Self {
+        let specs = env::var("strategies").unwrap_or_else(|_| "snipe_and_hold".to_string());
+
+        let mut strategies: Vec<(Box<dyn Strategy>, u64, EntryLimits)> = Vec::new();
+        for (idx, spec) in specs.split(';').map(str::trim).filter(|s| !s.is_empty()).enumerate() {
+            let (head, budget) = match spec.split_once('@') {
+                Some((head, budget)) => {
+                    let budget_sol = budget.parse::<f64>().expect("strategy budget must be a valid f64");
+                    (head, (budget_sol * LAMPORTS_PER_SOL as f64) as u64)
+                }
+                None => (spec, u64::MAX),
+            };
+            let (name, params) = head.split_once(':').unwrap_or((head, ""));
+            let params: HashMap<&str, &str> = params
+                .split(',')
+                .filter_map(|kv| kv.split_once('='))
+                .collect();
+            let limits = EntryLimits {
+                max_slot_lag: params
+                    .get("max_slot_lag")
+                    .map(|v| v.parse::<u64>().expect("max_slot_lag must be a valid u64")),
+                max_block0_snipers: params
+                    .get("max_block0_snipers")
+                    .map(|v| v.parse::<usize>().expect("max_block0_snipers must be a valid usize")),
+                max_insider_pct: params
+                    .get("max_insider_pct")
+                    .map(|v| v.parse::<f64>().expect("max_insider_pct must be a valid f64")),
+                min_holders: params
+                    .get("min_holders")
+                    .map(|v| v.parse::<usize>().expect("min_holders must be a valid usize")),
+                max_top10_pct: params
+                    .get("max_top10_pct")
+                    .map(|v| v.parse::<f64>().expect("max_top10_pct must be a valid f64")),
+                max_creator_pct: params
+                    .get("max_creator_pct")
+                    .map(|v| v.parse::<f64>().expect("max_creator_pct must be a valid f64")),
+            };
+
+            let investment = params
+                .get("investment")
+                .map(|v| v.parse::<f64>().expect("investment must be a valid f64") * LAMPORTS_PER_SOL as f64)
+                .unwrap_or(investment_lamported);
+            let strategy: Box<dyn Strategy> = match name {
+                "snipe_and_hold" => {
+                    let hold_secs = params
+                        .get("hold_secs")
+                        .map(|v| v.parse::<f64>().expect("hold_secs must be a valid f64"))
+                        .unwrap_or(10.0);
+                    let exit_top10_pct = params
+                        .get("exit_top10_pct")
+                        .map(|v| v.parse::<f64>().expect("exit_top10_pct must be a valid f64"));
+                    let exit_drop_pct = params
+                        .get("exit_drop_pct")
+                        .map(|v| v.parse::<f64>().expect("exit_drop_pct must be a valid f64"));
+                    Box::new(SnipeAndHold::new(
+                        format!("{}#{}", name, idx),
+                        investment as u64,
+                        (hold_secs * 1_000.0) as i64,
+                        exit_top10_pct,
+                        exit_drop_pct,
+                    ))
+                }
+                "copy_trade" => {
+                    // `,` already separates the params
+                    let wallets: HashSet<Pubkey> = params
+                        .get("wallets")
+                        .map(|v| {
+                            v.split('|')
+                                .filter(|w| !w.is_empty())
+                                .map(|w| Pubkey::from_str(w).expect("wallets must be valid pubkeys"))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    Box::new(CopyTrade::new(format!("{}#{}", name, idx), investment as u64, wallets))
+                }
+                other => panic!("unknown strategy: {}", other),
+            };
+            strategies.push((strategy, budget, limits));
+        }
+
+        StrategyRunner::new(strategies)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn name(&self, idx: usize) -> String {
+        self.slots[idx].strategy.lock().unwrap().name().to_string()
+    }
+
+    pub fn limits(&self, idx: usize) -> EntryLimits {
+        self.slots[idx].limits.lock().unwrap().clone()
+    }
+
+    /// Replaces the entry limits; buys already past their checks are not affected.
+    pub fn set_limits(&self, idx: usize, limits: EntryLimits) {
+        *self.slots[idx].limits.lock().unwrap() = limits;
+    }
+
+    pub fn budget_lamports(&self, idx: usize) -> u64 {
+        self.slots[idx].budget_lamports
+    }
+
+    pub fn investment(&self, idx: usize) -> Option<u64> {
+        self.slots[idx].strategy.lock().unwrap().investment()
+    }
+
+    /// Changes the size of the strategy's future buys. `false` if it has no fixed size.
+    pub fn set_investment(&self, idx: usize, lamports: u64) -> bool {
+        self.slots[idx].strategy.lock().unwrap().set_investment(lamports)
+    }
+
+    /// Runs `edit` on the strategy's copy list. `None` if it copies no wallets.
+    pub fn with_copy_list<R>(&self, idx: usize, edit: impl FnOnce(&mut HashSet<Pubkey>) -> R) -> Option<R> {
+        self.slots[idx].strategy.lock().unwrap().copy_list().map(edit)
+    }
+
+    pub fn committed_lamports(&self, idx: usize) -> u64 {
+        *self.slots[idx].committed_lamports.lock().unwrap()
+    }
+
+    pub fn on_create(&self, create: &CreateInfo) -> Vec<(usize, Intent)> {
+        self.collect_all(|strategy| strategy.on_create(create))
+    }
+
+    pub fn on_trade(&self, trade: &TradeEvent, candles: &CandleStore) -> Vec<(usize, Intent)> {
+        self.collect_all(|strategy| {
+            let mut intents = strategy.on_trade(trade, candles);
+            intents.extend(strategy.on_curve_update(
+                &trade.mint,
+                trade.get_virtual_sol_reserves(),
+                trade.get_virtual_token_reserves(),
+            ));
+            intents
+        })
+    }
+
+    pub fn on_fill(&self, idx: usize, fill: &Fill) -> Vec<(usize, Intent)> {
+        let intents = self.slots[idx].strategy.lock().unwrap().on_fill(fill);
+        self.admit(idx, intents)
+    }
+
+    pub fn on_sell_failed(&self, idx: usize, mint: &Pubkey, now_ms: i64) -> Vec<(usize, Intent)> {
+        let intents = self.slots[idx].strategy.lock().unwrap().on_sell_failed(mint, now_ms);
+        self.admit(idx, intents)
+    }
+
+    pub fn on_holders(&self, mint: &Pubkey, stats: &HolderStats) -> Vec<(usize, Intent)> {
+        self.collect_all(|strategy| strategy.on_holders(mint, stats))
+    }
+
+    pub fn on_timer(&self, now_ms: i64, candles: &CandleStore) -> Vec<(usize, Intent)> {
+        self.collect_all(|strategy| strategy.on_timer(now_ms, candles))
+    }
+
+    /// Claims a pending buy for sending. `None` means it was cancelled.
+    pub fn take_pending(&self, idx: usize, mint: &Pubkey) -> Option<u64> {
+        self.pending.lock().unwrap().remove(&(idx, *mint))
+    }
+
+    /// Commits budget for a position taken over from a previous run.
+    pub fn restore(&self, idx: usize, lamports: u64) {
+        *self.slots[idx].committed_lamports.lock().unwrap() += lamports;
+    }
+
+    /// Returns budget once a buy failed or a position was sold.
+    pub fn release(&self, idx: usize, lamports: u64) {
+        let mut committed = self.slots[idx].committed_lamports.lock().unwrap();
+        *committed = committed.saturating_sub(lamports);
+    }
+
+    fn collect_all<F>(&self, mut hook: F) -> Vec<(usize, Intent)>
+    where
+        F: FnMut(&mut dyn Strategy) -> Vec<Intent>,
+    {
+        let mut admitted = Vec::new();
+        for idx in 0..self.slots.len() {
+            let intents = hook(self.slots[idx].strategy.lock().unwrap().as_mut());
+            admitted.extend(self.admit(idx, intents));
+        }
+        admitted
+    }
+
+    // Applies budgets and cancels; what is returned should be executed.
+    fn admit(&self, idx: usize, intents: Vec<Intent>) -> Vec<(usize, Intent)> {
+        let slot = &self.slots[idx];
+        let mut admitted = Vec::new();
+
+        for intent in intents {
+            match intent {
+                Intent::Buy { mint, lamports } => {
+                    let mut committed = slot.committed_lamports.lock().unwrap();
+                    if committed.saturating_add(lamports) > slot.budget_lamports {
+                        println!(
+                            "{}:: {} over budget, skipping buy of {}",
+                            Local::now().format("%Y-%m-%d %H:%M:%S"),
+                            slot.strategy.lock().unwrap().name(),
+                            mint
+                        );
+                        continue;
+                    }
+                    *committed += lamports;
+                    self.pending.lock().unwrap().insert((idx, mint), lamports);
+                    admitted.push((idx, intent));
+                }
+                Intent::Cancel { mint } => {
+                    if let Some(lamports) = self.take_pending(idx, &mint) {
+                        self.release(idx, lamports);
+                    }
+                }
+                Intent::Sell { .. } => admitted.push((idx, intent)),
+            }
+        }
+
+        admitted
+    }
+}
@@ -0,0 +1,155 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{CreateInfo, Fill, Intent, Side, Strategy};
+use crate::utils::candles::CandleStore;
+use crate::utils::holders::HolderStats;
+use crate::utils::layouts::TradeEvent;
+
+// the drop exit looks at the last three 5s candles
+const DROP_INTERVAL: i64 = 5;
+const DROP_CANDLES: usize = 3;
+// buys that never fill are forgotten after this many newer ones
+const MAX_UNFILLED: usize = 256;
+// a failed exit is tried again this long after it was reported
+const SELL_RETRY_MS: i64 = 2_000;
+
+/// The original behaviour: buy every create, sell everything after a fixed
+/// hold, or earlier once the top holders own more than `exit_top10_pct` or the
+/// price fell more than `exit_drop_pct` over the last 15 seconds. A buy not
+/// yet sent is cancelled if the creator sells first. An exit only counts once
+/// its sell fills; a failed one is tried again.
+pub struct SnipeAndHold {
+    name: String,
+    investment_lamports: u64,
+    hold_ms: i64,
+    exit_top10_pct: Option<f64>,
+    exit_drop_pct: Option<f64>,
+    // mint -> time the position should be sold, kept until a sell fills
+    sell_at: HashMap<Pubkey, i64>,
+    // mints whose sell is out and not filled or failed yet
+    selling: HashSet<Pubkey>,
+    // mint -> creator, for buys decided but not filled yet, oldest first
+    unfilled: VecDeque<(Pubkey, Pubkey)>,
+}
+
+impl SnipeAndHold {
+    pub fn new(
+        name: String,
+        investment_lamports: u64,
+        hold_ms: i64,
+        exit_top10_pct: Option<f64>,
+        exit_drop_pct: Option<f64>,
+    ) -> Self {
+        SnipeAndHold {
+            name,
+            investment_lamports,
+            hold_ms,
+            exit_top10_pct,
+            exit_drop_pct,
+            sell_at: HashMap::new(),
+            selling: HashSet::new(),
+            unfilled: VecDeque::new(),
+        }
+    }
+
+    // held, and no sell of ours is out for it
+    fn can_sell(&self, mint: &Pubkey) -> bool {
+        self.sell_at.contains_key(mint) && !self.selling.contains(mint)
+    }
+
+    fn sell(&mut self, mint: Pubkey) -> Vec<Intent> {
+        self.selling.insert(mint);
+        vec![Intent::Sell { mint, pct: 1.0 }]
+    }
+}
+
+impl Strategy for SnipeAndHold {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn on_create(&mut self, create: &CreateInfo) -> Vec<Intent> {
+        if self.unfilled.len() >= MAX_UNFILLED {
+            self.unfilled.pop_front();
+        }
+        self.unfilled.push_back((create.mint, create.creator));
+        vec![Intent::Buy {
+            mint: create.mint,
+            lamports: self.investment_lamports,
+        }]
+    }
+
+    fn on_trade(&mut self, trade: &TradeEvent, candles: &CandleStore) -> Vec<Intent> {
+        if !trade.is_buy_bool() {
+            let creator_sold = self
+                .unfilled
+                .iter()
+                .position(|(mint, creator)| *mint == trade.mint && *creator == trade.user);
+            if let Some(idx) = creator_sold {
+                self.unfilled.remove(idx);
+                return vec![Intent::Cancel { mint: trade.mint }];
+            }
+        }
+
+        let max_drop = match self.exit_drop_pct {
+            Some(max_drop) if self.can_sell(&trade.mint) => max_drop,
+            _ => return Vec::new(),
+        };
+        match candles.momentum(&trade.mint, DROP_INTERVAL, DROP_CANDLES) {
+            Some(change) if change * 100.0 < -max_drop => self.sell(trade.mint),
+            _ => Vec::new(),
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Intent> {
+        match fill.side {
+            Side::Buy => {
+                self.unfilled.retain(|(mint, _)| *mint != fill.mint);
+                self.sell_at.insert(fill.mint, fill.time_ms + self.hold_ms);
+            }
+            Side::Sell => {
+                self.sell_at.remove(&fill.mint);
+                self.selling.remove(&fill.mint);
+            }
+        }
+        Vec::new()
+    }
+
+    fn on_sell_failed(&mut self, mint: &Pubkey, now_ms: i64) -> Vec<Intent> {
+        // sells we did not ask for leave the schedule as it was
+        if self.selling.remove(mint) {
+            if let Some(sell_at) = self.sell_at.get_mut(mint) {
+                *sell_at = now_ms + SELL_RETRY_MS;
+            }
+        }
+        Vec::new()
+    }
+
+    fn on_holders(&mut self, mint: &Pubkey, stats: &HolderStats) -> Vec<Intent> {
+        match self.exit_top10_pct {
+            Some(max_pct) if stats.top10_pct > max_pct && self.can_sell(mint) => self.sell(*mint),
+            _ => Vec::new(),
+        }
+    }
+
+    fn on_timer(&mut self, now_ms: i64, _candles: &CandleStore) -> Vec<Intent> {
+        let due: Vec<Pubkey> = self
+            .sell_at
+            .iter()
+            .filter(|(mint, sell_at)| now_ms >= **sell_at && !self.selling.contains(*mint))
+            .map(|(mint, _)| *mint)
+            .collect();
+
+        due.into_iter().flat_map(|mint| self.sell(mint)).collect()
+    }
+
+    fn investment(&self) -> Option<u64> {
+        Some(self.investment_lamports)
+    }
+
+    fn set_investment(&mut self, lamports: u64) -> bool {
+        self.investment_lamports = lamports;
+        true
+    }
+}
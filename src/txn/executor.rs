@@ -0,0 +1,179 @@
+use chrono::Local;
+use serde_json::json;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+};
+
+use super::nonce::NonceLease;
+use super::sender::TxSender;
+use super::spam_txn::spammer;
+
+#[derive(Clone, Debug)]
+pub struct SimFill {
+    /// Receive time of the notification that was being handled, in ms.
+    pub recv_ms: i64,
+    pub side: String,
+    pub mint: Pubkey,
+    pub token_amount: u64,
+    pub ladder_len: usize,
+}
+
+/// Stand-in for the network during replay: records what would have been sent.
+/// Its clock follows the receive times of the replayed messages.
+pub struct SimExecutor {
+    clock_ms: AtomicI64,
+    fills: Mutex<Vec<SimFill>>,
+    // ladders still to be turned away, for tests
+    rejects: AtomicUsize,
+    rejected: Mutex<Vec<SimFill>>,
+}
+
+impl SimExecutor {
+    pub fn new() -> Self {
+        SimExecutor {
+            clock_ms: AtomicI64::new(0),
+            fills: Mutex::new(Vec::new()),
+            rejects: AtomicUsize::new(0),
+            rejected: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Turns away the next `count` ladders as if no endpoint accepted them.
+    #[cfg(test)]
+    pub fn reject_next(&self, count: usize) {
+        self.rejects.store(count, Ordering::SeqCst);
+    }
+
+    /// Ladders turned away by `reject_next`.
+    #[cfg(test)]
+    pub fn rejected(&self) -> Vec<SimFill> {
+        self.rejected.lock().unwrap().clone()
+    }
+
+    pub fn set_clock_ms(&self, recv_ms: i64) {
+        self.clock_ms.store(recv_ms, Ordering::SeqCst);
+    }
+
+    pub fn fills(&self) -> Vec<SimFill> {
+        self.fills.lock().unwrap().clone()
+    }
+
+    pub fn write_fills(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        for fill in self.fills.lock().unwrap().iter() {
+            let line = json!({
+                "recv_ms": fill.recv_ms,
+                "side": fill.side,
+                "mint": fill.mint.to_string(),
+                "token_amount": fill.token_amount,
+                "ladder_len": fill.ladder_len,
+            });
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// One fee ladder: `instructions_vec` sent once per compute unit price in
+/// `prices_4_spam`. `side`, `mint` and `token_amount` only describe it.
+pub struct LadderRequest<'a> {
+    pub side: &'a str,
+    pub mint: &'a Pubkey,
+    pub token_amount: u64,
+    pub prices_4_spam: Vec<Instruction>,
+    pub client: &'a Arc<RpcClient>,
+    pub payer: &'a Arc<Keypair>,
+    pub instructions_vec: &'a [Instruction],
+    pub lookup_table: Option<&'a AddressLookupTableAccount>,
+    pub nonce: Option<&'a NonceLease>,
+}
+
+pub struct LadderResult {
+    /// The signature that was accepted, if any, and its index in the ladder.
+    pub accepted: Option<(Signature, usize)>,
+    /// Whether any variant was handed to the sender, accepted or not. A
+    /// rejected send may still have reached a leader.
+    pub attempted: bool,
+}
+
+pub enum Executor {
+    Live(Arc<dyn TxSender>),
+    Simulated(SimExecutor),
+}
+
+impl Executor {
+    pub fn is_live(&self) -> bool {
+        matches!(self, Executor::Live(_))
+    }
+
+    pub fn as_simulated(&self) -> Option<&SimExecutor> {
+        match self {
+            Executor::Simulated(sim) => Some(sim),
+            Executor::Live(_) => None,
+        }
+    }
+
+    /// Wall clock when live, the replayed message time when simulating.
+    pub fn now_ms(&self) -> i64 {
+        match self {
+            Executor::Live(_) => Local::now().timestamp_millis(),
+            Executor::Simulated(sim) => sim.clock_ms.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Reports whether a sent ladder landed, so the sender can attribute it.
+    pub async fn record_outcome(&self, signature: &Signature, landed: bool) {
+        if let Executor::Live(sender) = self {
+            sender.record_outcome(signature, landed).await;
+        }
+    }
+
+    /// Sends the fee ladder for the request's instructions, or records it when simulating.
+    pub async fn send_ladder(&self, request: LadderRequest<'_>) -> LadderResult {
+        match self {
+            Executor::Live(sender) => spammer(request, sender.as_ref()).await,
+            Executor::Simulated(sim) => {
+                let fill = SimFill {
+                    recv_ms: sim.clock_ms.load(Ordering::SeqCst),
+                    side: request.side.to_string(),
+                    mint: *request.mint,
+                    token_amount: request.token_amount,
+                    ladder_len: request.prices_4_spam.len(),
+                };
+                let reject = sim
+                    .rejects
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+                    .is_ok();
+                if reject {
+                    sim.rejected.lock().unwrap().push(fill);
+                    return LadderResult {
+                        accepted: None,
+                        attempted: true,
+                    };
+                }
+                println!(
+                    "{}:: [sim] {} {} tokens of {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    fill.side,
+                    fill.token_amount,
+                    fill.mint
+                );
+                sim.fills.lock().unwrap().push(fill);
+                LadderResult {
+                    accepted: Some((Signature::default(), 0)),
+                    attempted: true,
+                }
+            }
+        }
+    }
+}
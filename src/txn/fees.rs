@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction};
+use tokio::time::{sleep, Duration};
+
+use super::tuner::{FeeTuner, LadderOutcome};
+use crate::utils::constants::PUMPFUN_FEE_RECIPENT;
+
+/// Fee ladder expressed as percentiles of recent prioritization fees.
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct FeeConfig {
+    pub percentiles: Vec<f64>,
+    pub sell_percentiles: Vec<f64>,
+    pub rug_percentiles: Vec<f64>,
+    /// Most a single variant may pay in priority fees, in lamports.
+    pub cap_lamports: u64,
+    /// How many of the most recent slots the percentiles are taken over.
+    pub window_slots: usize,
+    pub sample_interval: Duration,
+}
+
+// accepts "p50,p90,p99" as well as "50,90,99"
+fn parse_percentiles(key: &str, value: &str) -> Vec<f64> {
+    value
+        .split(',')
+        .map(|p| p.trim().trim_start_matches('p'))
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let p = p
+                .parse::<f64>()
+                .unwrap_or_else(|_| panic!("{} must be a list of percentiles", key));
+            assert!((0.0..=100.0).contains(&p), "{} must be between 0 and 100", key);
+            p
+        })
+        .collect()
+}
+
+impl FeeConfig {
+    /// `None` unless `fee_percentiles` is set, in which case the static ladder is used.
+    pub fn from_env() -> Option<Self> {
+        let percentiles = parse_percentiles("fee_percentiles", &env::var("fee_percentiles").ok()?);
+
+        let sell_percentiles = env::var("sell_fee_percentiles")
+            .map(|v| parse_percentiles("sell_fee_percentiles", &v))
+            .unwrap_or_else(|_| percentiles.clone());
+
+        let rug_percentiles = env::var("rug_fee_percentiles")
+            .map(|v| parse_percentiles("rug_fee_percentiles", &v))
+            .unwrap_or(vec![95.0, 99.0]);
+
+        let cap_lamports = env::var("fee_cap_lamports")
+            .map(|v| v.parse::<u64>().expect("fee_cap_lamports must be a valid u64"))
+            .unwrap_or(1_000_000);
+
+        let window_slots = env::var("fee_window_slots")
+            .map(|v| v.parse::<usize>().expect("fee_window_slots must be a valid usize"))
+            .unwrap_or(150);
+
+        Some(FeeConfig {
+            percentiles,
+            sell_percentiles,
+            rug_percentiles,
+            cap_lamports,
+            window_slots,
+            sample_interval: Duration::from_secs(2),
+        })
+    }
+}
+
+/// Rolling per-slot prioritization fees paid by transactions write-locking the
+/// pump.fun fee recipient, which every buy and sell does.
+pub struct FeeOracle {
+    window_slots: usize,
+    // slot -> fee in micro-lamports per CU
+    samples: Mutex<BTreeMap<u64, u64>>,
+}
+
+impl FeeOracle {
+    pub fn new(window_slots: usize) -> Self {
+        FeeOracle {
+            window_slots,
+            samples: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Samples `getRecentPrioritizationFees` every `interval` in the background.
+    pub fn spawn(client: Arc<RpcClient>, window_slots: usize, interval: Duration) -> Arc<Self> {
+        let oracle = Arc::new(FeeOracle::new(window_slots));
+        let sampler = oracle.clone();
+        tokio::spawn(async move {
+            loop {
+                // fees only count for accounts the transactions write-lock
+                match client.get_recent_prioritization_fees(&[PUMPFUN_FEE_RECIPENT]).await {
+                    Ok(fees) => sampler.record(fees.iter().map(|f| (f.slot, f.prioritization_fee))),
+                    Err(e) => eprintln!("Failed to fetch prioritization fees: {:?}", e),
+                }
+                sleep(interval).await;
+            }
+        });
+        oracle
+    }
+
+    /// Slots without a fee say nothing about what it takes to land, so they are skipped.
+    pub fn record(&self, fees: impl Iterator<Item = (u64, u64)>) {
+        let mut samples = self.samples.lock().unwrap();
+        samples.extend(fees.filter(|(_, fee)| *fee > 0));
+        while samples.len() > self.window_slots {
+            samples.pop_first();
+        }
+    }
+
+    /// Fee at percentile `p` (0-100) over the window, `None` before the first non-zero sample.
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let mut fees: Vec<u64> = self.samples.lock().unwrap().values().copied().collect();
+        if fees.is_empty() {
+            return None;
+        }
+        fees.sort_unstable();
+        let rank = ((p / 100.0) * (fees.len() - 1) as f64).round() as usize;
+        Some(fees[rank.min(fees.len() - 1)])
+    }
+}
+
+/// The buy, sell and emergency fee ladders. The buy ladder comes from the
+/// tuner when tuning is on; otherwise each is priced from the oracle when one
+/// is configured and has samples, falling back to the static `budget_price`
+/// ladders. Regular sells use the static buy ladder then.
+pub struct FeeLadders {
+    unit_limit: u32,
+    static_buy: Vec<Instruction>,
+    static_rug: Vec<Instruction>,
+    dynamic: Option<(Arc<FeeOracle>, FeeConfig)>,
+    tuner: Option<FeeTuner>,
+}
+
+impl FeeLadders {
+    pub fn new(
+        unit_limit: u32,
+        static_buy: Vec<Instruction>,
+        static_rug: Vec<Instruction>,
+        dynamic: Option<(Arc<FeeOracle>, FeeConfig)>,
+        tuner: Option<FeeTuner>,
+    ) -> Self {
+        FeeLadders {
+            unit_limit,
+            static_buy,
+            static_rug,
+            dynamic,
+            tuner,
+        }
+    }
+
+    pub fn buy(&self) -> Vec<Instruction> {
+        if let Some(tuner) = &self.tuner {
+            return tuner.ladder();
+        }
+        self.dynamic
+            .as_ref()
+            .and_then(|(oracle, config)| self.priced(oracle, config, &config.percentiles))
+            .unwrap_or_else(|| self.static_buy.clone())
+    }
+
+    /// Never tuned, the tuner learns from buy landings only.
+    pub fn sell(&self) -> Vec<Instruction> {
+        self.dynamic
+            .as_ref()
+            .and_then(|(oracle, config)| self.priced(oracle, config, &config.sell_percentiles))
+            .unwrap_or_else(|| self.static_buy.clone())
+    }
+
+    pub fn rug(&self) -> Vec<Instruction> {
+        self.dynamic
+            .as_ref()
+            .and_then(|(oracle, config)| self.priced(oracle, config, &config.rug_percentiles))
+            .unwrap_or_else(|| self.static_rug.clone())
+    }
+
+    /// Feeds a buy ladder's outcome to the tuner, if tuning is on.
+    pub fn record_buy(&self, outcome: LadderOutcome) {
+        if let Some(tuner) = &self.tuner {
+            tuner.record(outcome);
+        }
+    }
+
+    fn priced(&self, oracle: &FeeOracle, config: &FeeConfig, percentiles: &[f64]) -> Option<Vec<Instruction>> {
+        // the cap is per transaction, so it depends on how many CUs we ask for
+        let max_price = (config.cap_lamports as u128 * 1_000_000 / self.unit_limit.max(1) as u128) as u64;
+
+        let mut prices: Vec<u64> = Vec::new();
+        for p in percentiles {
+            let mut price = oracle.percentile(*p)?.min(max_price);
+            // identical prices would sign identical transactions
+            if let Some(last) = prices.last() {
+                if price <= *last {
+                    price = last + 1;
+                }
+            }
+            if price > max_price {
+                break;
+            }
+            prices.push(price);
+        }
+        if prices.is_empty() {
+            return None;
+        }
+
+        Some(
+            prices
+                .into_iter()
+                .map(ComputeBudgetInstruction::set_compute_unit_price)
+                .collect(),
+        )
+    }
+}
+
+pub fn compute_unit_price(ix: &Instruction) -> Option<u64> {
+    compute_unit_price_data(&ix.data)
+}
+
+// ComputeBudgetInstruction::SetComputeUnitPrice is tag 3 followed by a u64 (micro-lamports)
+pub fn compute_unit_price_data(data: &[u8]) -> Option<u64> {
+    match data.split_first() {
+        Some((3, rest)) if rest.len() >= 8 => Some(u64::from_le_bytes(rest[..8].try_into().ok()?)),
+        _ => None,
+    }
+}
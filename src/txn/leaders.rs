@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, quic::QUIC_PORT_OFFSET};
+use tokio::time::{sleep, Duration};
+
+use crate::utils::slots::SlotClock;
+
+// a leader produces this many consecutive slots
+const SLOTS_PER_LEADER: u64 = 4;
+// how far ahead the schedule is fetched, and when it is fetched again
+const SCHEDULE_WINDOW: u64 = 200;
+const SCHEDULE_REFETCH_MARGIN: u64 = 50;
+const SLOT_POLL: Duration = Duration::from_millis(400);
+const NODES_REFRESH_SECS: u64 = 60;
+
+struct Schedule {
+    start_slot: u64,
+    leaders: Vec<Pubkey>,
+}
+
+/// Follows slot progression and the leader schedule, and knows each
+/// validator's TPU QUIC address from `getClusterNodes`.
+pub struct LeaderTracker {
+    polled_slot: AtomicU64,
+    clock: Arc<SlotClock>,
+    schedule: Mutex<Schedule>,
+    tpu_quic: Mutex<HashMap<Pubkey, SocketAddr>>,
+}
+
+impl LeaderTracker {
+    pub fn new(clock: Arc<SlotClock>) -> Self {
+        LeaderTracker {
+            polled_slot: AtomicU64::new(0),
+            clock,
+            schedule: Mutex::new(Schedule {
+                start_slot: 0,
+                leaders: Vec::new(),
+            }),
+            tpu_quic: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Starts polling slot, schedule and cluster nodes in the background.
+    /// `clock` is read alongside the polled slot.
+    pub fn spawn(client: Arc<RpcClient>, clock: Arc<SlotClock>) -> Arc<Self> {
+        let tracker = Arc::new(LeaderTracker::new(clock));
+        let follower = tracker.clone();
+        tokio::spawn(async move {
+            follower.follow(client).await;
+        });
+        tracker
+    }
+
+    async fn follow(&self, client: Arc<RpcClient>) {
+        let mut polls: u64 = 0;
+        loop {
+            if polls.is_multiple_of(NODES_REFRESH_SECS * 1000 / SLOT_POLL.as_millis() as u64) {
+                self.refresh_nodes(&client).await;
+            }
+            polls += 1;
+
+            match client.get_slot_with_commitment(CommitmentConfig::processed()).await {
+                Ok(slot) => self.set_slot(slot),
+                Err(e) => eprintln!("Failed to fetch slot: {:?}", e),
+            }
+
+            let slot = self.slot();
+            if self.needs_schedule(slot) {
+                match client.get_slot_leaders(slot, SCHEDULE_WINDOW).await {
+                    Ok(leaders) => {
+                        let mut schedule = self.schedule.lock().unwrap();
+                        schedule.start_slot = slot;
+                        schedule.leaders = leaders;
+                    }
+                    Err(e) => eprintln!("Failed to fetch slot leaders: {:?}", e),
+                }
+            }
+
+            sleep(SLOT_POLL).await;
+        }
+    }
+
+    async fn refresh_nodes(&self, client: &RpcClient) {
+        let nodes = match client.get_cluster_nodes().await {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                eprintln!("Failed to fetch cluster nodes: {:?}", e);
+                return;
+            }
+        };
+
+        let mut tpu_quic = self.tpu_quic.lock().unwrap();
+        tpu_quic.clear();
+        for node in nodes {
+            let pubkey = match Pubkey::from_str(&node.pubkey) {
+                Ok(pubkey) => pubkey,
+                Err(_) => continue,
+            };
+            // older nodes only advertise the UDP port, QUIC sits at a fixed offset
+            let addr = node.tpu_quic.or_else(|| {
+                node.tpu
+                    .map(|tpu| SocketAddr::new(tpu.ip(), tpu.port() + QUIC_PORT_OFFSET))
+            });
+            if let Some(addr) = addr {
+                tpu_quic.insert(pubkey, addr);
+            }
+        }
+    }
+
+    fn needs_schedule(&self, slot: u64) -> bool {
+        let schedule = self.schedule.lock().unwrap();
+        schedule.leaders.is_empty()
+            || slot < schedule.start_slot
+            || slot + SCHEDULE_REFETCH_MARGIN >= schedule.start_slot + schedule.leaders.len() as u64
+    }
+
+    fn set_slot(&self, slot: u64) {
+        self.polled_slot.fetch_max(slot, Ordering::SeqCst);
+    }
+
+    /// Newest slot from either the poll or the slot clock. The clock follows
+    /// `slotSubscribe` and usually moves before the next poll comes back.
+    pub fn slot(&self) -> u64 {
+        self.polled_slot.load(Ordering::SeqCst).max(self.clock.slot())
+    }
+
+    /// The next `count` distinct leaders from the current slot on.
+    pub fn upcoming_leaders(&self, count: usize) -> Vec<Pubkey> {
+        let slot = self.slot();
+        let schedule = self.schedule.lock().unwrap();
+        let mut leaders = Vec::new();
+
+        let mut next = slot.max(schedule.start_slot);
+        while leaders.len() < count {
+            let idx = match next.checked_sub(schedule.start_slot) {
+                Some(idx) if (idx as usize) < schedule.leaders.len() => idx as usize,
+                _ => break,
+            };
+            let leader = schedule.leaders[idx];
+            if !leaders.contains(&leader) {
+                leaders.push(leader);
+            }
+            // leader rotations start on multiples of four
+            next = (next / SLOTS_PER_LEADER + 1) * SLOTS_PER_LEADER;
+        }
+
+        leaders
+    }
+
+    /// TPU QUIC addresses of the next `count` leaders that advertise one.
+    pub fn upcoming_leader_addrs(&self, count: usize) -> Vec<SocketAddr> {
+        let tpu_quic = self.tpu_quic.lock().unwrap();
+        self.upcoming_leaders(count)
+            .iter()
+            .filter_map(|leader| tpu_quic.get(leader).copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upcoming_leaders_follow_the_slot_clock() {
+        let clock = Arc::new(SlotClock::new());
+        let tracker = LeaderTracker::new(clock.clone());
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        {
+            let mut schedule = tracker.schedule.lock().unwrap();
+            schedule.start_slot = 100;
+            schedule.leaders = [[a; 4], [b; 4], [c; 4]].concat();
+        }
+        tracker.set_slot(100);
+        assert_eq!(tracker.upcoming_leaders(2), vec![a, b]);
+
+        // the WebSocket is ahead of the last poll
+        clock.observe(105);
+        assert_eq!(tracker.slot(), 105);
+        assert_eq!(tracker.upcoming_leaders(2), vec![b, c]);
+    }
+}
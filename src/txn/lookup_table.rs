@@ -0,0 +1,134 @@
+use chrono::Local;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::AddressLookupTable,
+        AddressLookupTableAccount,
+    },
+    commitment_config::CommitmentConfig,
+    compute_budget,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_program,
+    sysvar::rent,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::utils::constants::*;
+
+// addresses per extend instruction, keeps each extend under the packet size
+const EXTEND_BATCH: usize = 20;
+
+/// Accounts every pump.fun buy and sell carries.
+pub fn static_accounts() -> Vec<Pubkey> {
+    vec![
+        PUMPFUN_GLOBAL,
+        PUMPFUN_FEE_RECIPENT,
+        PUMPFUN_EVENT_AUTHORITY,
+        PUMPFUN_PROGRAM,
+        system_program::id(),
+        TOKEN_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent::id(),
+        compute_budget::id(),
+    ]
+}
+
+pub async fn load_lookup_table(client: &RpcClient, address: &Pubkey) -> Result<AddressLookupTableAccount, String> {
+    let data = client.get_account_data(address).await.map_err(|e| e.to_string())?;
+    let table = AddressLookupTable::deserialize(&data).map_err(|e| e.to_string())?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Signs `instructions` as a v0 transaction resolving accounts through
+/// `lookup_table`, or as a legacy transaction without one.
+pub fn build_transaction(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    lookup_table: Option<&AddressLookupTableAccount>,
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction, String> {
+    match lookup_table {
+        Some(table) => {
+            let message = v0::Message::try_compile(
+                &payer.pubkey(),
+                instructions,
+                std::slice::from_ref(table),
+                recent_blockhash,
+            )
+            .map_err(|e| e.to_string())?;
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).map_err(|e| e.to_string())
+        }
+        None => Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        )
+        .into()),
+    }
+}
+
+async fn send(client: &RpcClient, payer: &Keypair, ixs: &[Instruction]) -> Result<String, String> {
+    let recent_blockhash = client.get_latest_blockhash().await.map_err(|e| e.to_string())?;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&payer.pubkey()), &[payer], recent_blockhash);
+    client
+        .send_and_confirm_transaction(&tx)
+        .await
+        .map(|sig| sig.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Creates a table owned by `payer` and fills it with the static pump.fun accounts.
+pub async fn create_table(client: &RpcClient, payer: &Keypair) -> Result<Pubkey, String> {
+    let recent_slot = client
+        .get_slot_with_commitment(CommitmentConfig::finalized())
+        .await
+        .map_err(|e| e.to_string())?;
+    let (create_ix, address) = create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+
+    let sig = send(client, payer, &[create_ix]).await?;
+    println!(
+        "{}:: created lookup table {}: {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        address,
+        sig
+    );
+
+    extend_table(client, payer, &address, static_accounts()).await?;
+    Ok(address)
+}
+
+/// Adds whichever of `addresses` the table does not hold yet.
+pub async fn extend_table(client: &RpcClient, payer: &Keypair, table: &Pubkey, addresses: Vec<Pubkey>) -> Result<usize, String> {
+    let existing = load_lookup_table(client, table).await?.addresses;
+    let mut missing: Vec<Pubkey> = Vec::new();
+    for address in addresses {
+        if !existing.contains(&address) && !missing.contains(&address) {
+            missing.push(address);
+        }
+    }
+
+    for batch in missing.chunks(EXTEND_BATCH) {
+        let ix = extend_lookup_table(*table, payer.pubkey(), Some(payer.pubkey()), batch.to_vec());
+        let sig = send(client, payer, &[ix]).await?;
+        println!(
+            "{}:: added {} addresses to {}: {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            batch.len(),
+            table,
+            sig
+        );
+    }
+
+    Ok(missing.len())
+}
@@ -1 +1,8 @@
+pub mod executor;
+pub mod fees;
+pub mod leaders;
+pub mod lookup_table;
+pub mod nonce;
+pub mod sender;
 pub mod spam_txn;
+pub mod tuner;
@@ -0,0 +1,202 @@
+use chrono::Local;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use solana_client::{nonblocking::rpc_client::RpcClient, nonce_utils};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+
+// a sent ladder whose nonce has not moved after this long is cancelled
+const CANCEL_AFTER: Duration = Duration::from_secs(20);
+
+/// A nonce account handed out for one ladder. Every variant of the ladder is
+/// signed over the same nonce value with `advance_nonce_account` first, so
+/// the first variant to land invalidates all the others.
+///
+/// Only the nonce is ready before a launch. The signature covers every
+/// account of the message, the mint, its curve and the ATAs among them, so
+/// no part of a variant can be signed before the mint is known. What the
+/// lease saves is the blockhash round trip once it is.
+#[derive(Clone, Debug)]
+pub struct NonceLease {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+}
+
+impl NonceLease {
+    pub fn advance_ix(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.account, &self.authority)
+    }
+}
+
+struct NonceSlot {
+    account: Pubkey,
+    authority: Pubkey,
+    // `None` until the account was read
+    blockhash: Option<Hash>,
+    // set while a ladder signed over the current value may still land
+    in_use_since: Option<Instant>,
+    leased: bool,
+}
+
+/// Nonce accounts for durable-nonce sending. A slot is leased for one ladder
+/// and only becomes free again once its on-chain value has advanced.
+pub struct NoncePool {
+    slots: Mutex<Vec<NonceSlot>>,
+}
+
+async fn fetch_nonce(client: &RpcClient, account: &Pubkey) -> Result<(Pubkey, Hash), String> {
+    let account_data =
+        nonce_utils::nonblocking::get_account_with_commitment(client, account, CommitmentConfig::confirmed())
+            .await
+            .map_err(|e| e.to_string())?;
+    let data = nonce_utils::data_from_account(&account_data).map_err(|e| e.to_string())?;
+    Ok((data.authority, data.blockhash()))
+}
+
+impl NoncePool {
+    pub async fn load(client: &RpcClient, accounts: Vec<Pubkey>) -> Self {
+        let mut slots = Vec::new();
+        for account in accounts {
+            match fetch_nonce(client, &account).await {
+                Ok((authority, blockhash)) => slots.push(NonceSlot {
+                    account,
+                    authority,
+                    blockhash: Some(blockhash),
+                    in_use_since: None,
+                    leased: false,
+                }),
+                Err(e) => eprintln!("Failed to load nonce account {}: {}", account, e),
+            }
+        }
+
+        NoncePool {
+            slots: Mutex::new(slots),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+
+    /// A free nonce that `authority` can advance, if any.
+    pub fn lease(&self, authority: &Pubkey) -> Option<NonceLease> {
+        let mut slots = self.slots.lock().unwrap();
+        let slot = slots
+            .iter_mut()
+            .find(|s| s.authority == *authority && !s.leased && s.in_use_since.is_none() && s.blockhash.is_some())?;
+        slot.leased = true;
+        Some(NonceLease {
+            account: slot.account,
+            authority: slot.authority,
+            blockhash: slot.blockhash?,
+        })
+    }
+
+    /// Returns a lease. If nothing was sent the nonce is free again right away,
+    /// otherwise it waits for the on-chain value to move.
+    pub fn release(&self, lease: &NonceLease, sent: bool) {
+        let mut slots = self.slots.lock().unwrap();
+        if let Some(slot) = slots.iter_mut().find(|s| s.account == lease.account) {
+            slot.leased = false;
+            if sent {
+                slot.in_use_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Picks up advanced nonce values. A nonce that is still unchanged long
+    /// after its ladder went out is advanced by hand, so none of the ladder's
+    /// variants can land late; `signer_for` supplies the authority's keypair.
+    pub async fn refresh<'a, F>(&self, client: &RpcClient, signer_for: F)
+    where
+        F: Fn(&Pubkey) -> Option<&'a Keypair>,
+    {
+        let pending: Vec<(Pubkey, Pubkey, Option<Hash>, Option<Instant>)> = self
+            .slots
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.in_use_since.is_some() || s.blockhash.is_none())
+            .map(|s| (s.account, s.authority, s.blockhash, s.in_use_since))
+            .collect();
+
+        for (account, authority, old, in_use_since) in pending {
+            let current = match fetch_nonce(client, &account).await {
+                Ok((_, blockhash)) => blockhash,
+                Err(_) => continue,
+            };
+
+            if Some(current) != old {
+                let mut slots = self.slots.lock().unwrap();
+                if let Some(slot) = slots.iter_mut().find(|s| s.account == account) {
+                    slot.blockhash = Some(current);
+                    slot.in_use_since = None;
+                }
+                continue;
+            }
+
+            let stale = in_use_since.map(|t| t.elapsed() > CANCEL_AFTER).unwrap_or(false);
+            if let (true, Some(signer)) = (stale, signer_for(&authority)) {
+                match cancel(client, signer, &account).await {
+                    Ok(sig) => println!(
+                        "{}:: advanced nonce {} to cancel its ladder: {}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        account,
+                        sig
+                    ),
+                    Err(e) => eprintln!("Failed to advance nonce {}: {}", account, e),
+                }
+            }
+        }
+    }
+}
+
+async fn cancel(client: &RpcClient, authority: &Keypair, account: &Pubkey) -> Result<String, String> {
+    let ix = system_instruction::advance_nonce_account(account, &authority.pubkey());
+    let recent_blockhash = client.get_latest_blockhash().await.map_err(|e| e.to_string())?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&authority.pubkey()), &[authority], recent_blockhash);
+    client
+        .send_and_confirm_transaction(&tx)
+        .await
+        .map(|sig| sig.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Creates `count` nonce accounts with `authority` as their authority, funded by `funder`.
+pub async fn create_nonce_accounts(
+    client: &RpcClient,
+    funder: &Keypair,
+    authority: &Pubkey,
+    count: usize,
+) -> Result<Vec<Pubkey>, String> {
+    let rent = client
+        .get_minimum_balance_for_rent_exemption(NonceState::size())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut created = Vec::new();
+    for _ in 0..count {
+        let nonce = Keypair::new();
+        let ixs = system_instruction::create_nonce_account(&funder.pubkey(), &nonce.pubkey(), authority, rent);
+        let recent_blockhash = client.get_latest_blockhash().await.map_err(|e| e.to_string())?;
+        let tx = Transaction::new_signed_with_payer(&ixs, Some(&funder.pubkey()), &[funder, &nonce], recent_blockhash);
+        client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        created.push(nonce.pubkey());
+    }
+
+    Ok(created)
+}
@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::{Keypair, Signature}, signer::Signer,
+    system_instruction, transaction::{Transaction, VersionedTransaction},
+};
+
+use super::{SendError, TxSender};
+
+// landing is checked seconds after the send; older bundle ids are dropped unchecked
+const BUNDLE_TTL: Duration = Duration::from_secs(120);
+const MAX_BUNDLES: usize = 1_000;
+
+/// Submits each transaction as a bundle to a block engine, followed by a
+/// tip transfer paid by `tipper` so the bundle is considered at all.
+pub struct BundleSender {
+    engine: RpcClient,
+    client: Arc<RpcClient>,
+    tipper: Arc<Keypair>,
+    tip_lamports: u64,
+    tip_accounts: Mutex<Vec<Pubkey>>,
+    // bundle id and submission time per signature, for landing checks
+    bundles: Mutex<HashMap<Signature, (String, Instant)>>,
+}
+
+impl BundleSender {
+    /// `block_engine` is a host such as `ny.mainnet.block-engine.jito.wtf`.
+    pub fn new(block_engine: &str, client: Arc<RpcClient>, tipper: Arc<Keypair>, tip_lamports: u64) -> Self {
+        BundleSender {
+            engine: RpcClient::new(format!("https://{}/api/v1/bundles", block_engine)),
+            client,
+            tipper,
+            tip_lamports,
+            tip_accounts: Mutex::new(Vec::new()),
+            bundles: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // a random one of the engine's tip accounts, spreading tips avoids write-lock contention
+    async fn tip_account(&self) -> Result<Pubkey, String> {
+        if let Some(account) = self.tip_accounts.lock().unwrap().choose(&mut rand::thread_rng()) {
+            return Ok(*account);
+        }
+
+        let accounts: Vec<String> = self
+            .engine
+            .send(RpcRequest::Custom { method: "getTipAccounts" }, json!([]))
+            .await
+            .map_err(|e| e.to_string())?;
+        let accounts: Vec<Pubkey> = accounts.iter().filter_map(|a| Pubkey::from_str(a).ok()).collect();
+        let account = *accounts
+            .choose(&mut rand::thread_rng())
+            .ok_or("block engine returned no tip accounts")?;
+        *self.tip_accounts.lock().unwrap() = accounts;
+        Ok(account)
+    }
+
+    async fn tip_transaction(&self, tx: &VersionedTransaction) -> Result<Transaction, String> {
+        // a nonce value is not a recent blockhash, so the tip needs its own
+        let recent_blockhash = if tx.uses_durable_nonce() {
+            self.client
+                .get_latest_blockhash_with_commitment(CommitmentConfig::processed())
+                .await
+                .map_err(|e| e.to_string())?
+                .0
+        } else {
+            *tx.message.recent_blockhash()
+        };
+
+        let tip_ix = system_instruction::transfer(&self.tipper.pubkey(), &self.tip_account().await?, self.tip_lamports);
+        Ok(Transaction::new_signed_with_payer(
+            &[tip_ix],
+            Some(&self.tipper.pubkey()),
+            &[self.tipper.as_ref()],
+            recent_blockhash,
+        ))
+    }
+}
+
+fn encode<T: serde::Serialize>(tx: &T) -> Result<String, SendError> {
+    bincode::serialize(tx)
+        .map(base64::encode)
+        .map_err(|e| SendError::Rejected(e.to_string()))
+}
+
+#[async_trait]
+impl TxSender for BundleSender {
+    fn name(&self) -> &str {
+        "bundle"
+    }
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError> {
+        let tip = self.tip_transaction(tx).await.map_err(SendError::Other)?;
+        let params = json!([[encode(tx)?, encode(&tip)?], { "encoding": "base64" }]);
+
+        let bundle_id: String = self
+            .engine
+            .send(RpcRequest::Custom { method: "sendBundle" }, params)
+            .await
+            .map_err(|e| SendError::Rejected(e.to_string()))?;
+
+        let signature = tx.signatures[0];
+        let mut bundles = self.bundles.lock().unwrap();
+        bundles.retain(|_, (_, sent_at)| sent_at.elapsed() < BUNDLE_TTL);
+        if bundles.len() >= MAX_BUNDLES {
+            let oldest = bundles.iter().min_by_key(|(_, (_, sent_at))| *sent_at).map(|(sig, _)| *sig);
+            if let Some(oldest) = oldest {
+                bundles.remove(&oldest);
+            }
+        }
+        bundles.insert(signature, (bundle_id, Instant::now()));
+        Ok(signature)
+    }
+
+    async fn confirms_landing(&self, signature: &Signature) -> bool {
+        let bundle_id = match self.bundles.lock().unwrap().remove(signature) {
+            Some((id, _)) => id,
+            None => return false,
+        };
+
+        let statuses: Value = match self
+            .engine
+            .send(RpcRequest::Custom { method: "getBundleStatuses" }, json!([[bundle_id]]))
+            .await
+        {
+            Ok(statuses) => statuses,
+            Err(_) => return false,
+        };
+
+        // an unknown bundle comes back as null, a landed one with `err: {"Ok": null}`
+        statuses["value"]
+            .as_array()
+            .and_then(|v| v.first())
+            .map(|status| !status.is_null() && status["err"].get("Err").is_none())
+            .unwrap_or(false)
+    }
+}
@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use chrono::Local;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use super::{SendError, TxSender};
+
+#[derive(Clone, Debug, Default)]
+pub struct BackendStats {
+    pub sent: u64,
+    pub accepted: u64,
+    /// Times this backend reported acceptance before any other.
+    pub first: u64,
+    pub landed: u64,
+}
+
+// outcomes come in seconds after the send; whatever is still unresolved after
+// this long never will be, e.g. a buy whose confirmation timed out
+const SUBMISSION_TTL: Duration = Duration::from_secs(120);
+const MAX_SUBMISSIONS: usize = 1_000;
+
+struct Submission {
+    // backend names in the order they accepted
+    accepted: Vec<String>,
+    sent_at: Instant,
+}
+
+/// Submits the same signed transaction through every backend at once and
+/// returns as soon as one accepts. Keeps track of which backend accepted
+/// first and, once the outcome is known, which one the transaction landed through.
+pub struct FanoutSender {
+    backends: Vec<Arc<dyn TxSender>>,
+    submissions: Arc<Mutex<HashMap<Signature, Submission>>>,
+    stats: Arc<Mutex<HashMap<String, BackendStats>>>,
+}
+
+impl FanoutSender {
+    pub fn new(backends: Vec<Arc<dyn TxSender>>) -> Self {
+        FanoutSender {
+            backends,
+            submissions: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn stats(&self) -> Vec<(String, BackendStats)> {
+        let stats = self.stats.lock().unwrap();
+        self.backends
+            .iter()
+            .map(|b| (b.name().to_string(), stats.get(b.name()).cloned().unwrap_or_default()))
+            .collect()
+    }
+}
+
+fn record_accept(
+    submissions: &Mutex<HashMap<Signature, Submission>>,
+    stats: &Mutex<HashMap<String, BackendStats>>,
+    signature: Signature,
+    backend: &str,
+) {
+    let mut submissions = submissions.lock().unwrap();
+    if !submissions.contains_key(&signature) {
+        submissions.retain(|_, s| s.sent_at.elapsed() < SUBMISSION_TTL);
+        if submissions.len() >= MAX_SUBMISSIONS {
+            let oldest = submissions.iter().min_by_key(|(_, s)| s.sent_at).map(|(sig, _)| *sig);
+            if let Some(oldest) = oldest {
+                submissions.remove(&oldest);
+            }
+        }
+    }
+    let submission = submissions.entry(signature).or_insert_with(|| Submission {
+        accepted: Vec::new(),
+        sent_at: Instant::now(),
+    });
+
+    let mut stats = stats.lock().unwrap();
+    let entry = stats.entry(backend.to_string()).or_default();
+    entry.accepted += 1;
+    if submission.accepted.is_empty() {
+        entry.first += 1;
+    }
+    submission.accepted.push(backend.to_string());
+}
+
+#[async_trait]
+impl TxSender for FanoutSender {
+    fn name(&self) -> &str {
+        "fanout"
+    }
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError> {
+        let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+        for backend in &self.backends {
+            self.stats.lock().unwrap().entry(backend.name().to_string()).or_default().sent += 1;
+
+            let backend = backend.clone();
+            let tx = tx.clone();
+            let results_tx = results_tx.clone();
+            tokio::spawn(async move {
+                let result = backend.send(&tx).await;
+                let _ = results_tx.send((backend.name().to_string(), result));
+            });
+        }
+        drop(results_tx);
+
+        let mut last_error = None;
+        while let Some((backend, result)) = results_rx.recv().await {
+            match result {
+                Ok(signature) => {
+                    record_accept(&self.submissions, &self.stats, signature, &backend);
+
+                    // later acceptances are still recorded, just not waited on
+                    let submissions = self.submissions.clone();
+                    let stats = self.stats.clone();
+                    tokio::spawn(async move {
+                        while let Some((backend, result)) = results_rx.recv().await {
+                            if let Ok(signature) = result {
+                                record_accept(&submissions, &stats, signature, &backend);
+                            }
+                        }
+                    });
+                    return Ok(signature);
+                }
+                Err(e) => {
+                    eprintln!("{} did not accept the transaction: {:?}", backend, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| SendError::Other("no sender backends configured".to_string())))
+    }
+
+    async fn record_outcome(&self, signature: &Signature, landed: bool) {
+        let accepted = match self.submissions.lock().unwrap().remove(signature) {
+            Some(submission) => submission.accepted,
+            None => return,
+        };
+        if !landed || accepted.is_empty() {
+            return;
+        }
+
+        // a backend that can tell (bundles) wins, otherwise the first to accept gets the credit
+        let mut landed_via = accepted[0].clone();
+        for name in &accepted {
+            if let Some(backend) = self.backends.iter().find(|b| b.name() == name) {
+                if backend.confirms_landing(signature).await {
+                    landed_via = name.clone();
+                    break;
+                }
+            }
+        }
+        self.stats.lock().unwrap().entry(landed_via.clone()).or_default().landed += 1;
+
+        println!(
+            "{}:: {} first accepted by {}, landed via {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            signature,
+            accepted[0],
+            landed_via
+        );
+        for (name, stats) in self.stats() {
+            println!(
+                "    {}: sent {}, accepted {}, first {}, landed {}",
+                name, stats.sent, stats.accepted, stats.first, stats.landed
+            );
+        }
+    }
+}
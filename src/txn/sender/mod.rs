@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use std::env;
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::sol_to_lamports, signature::{Keypair, Signature}, transaction::VersionedTransaction};
+
+use crate::txn::leaders::LeaderTracker;
+use crate::utils::constants::BLOCK_ENGINE_URL;
+use crate::utils::slots::SlotClock;
+
+pub mod bundle;
+pub mod fanout;
+pub mod multi_rpc;
+pub mod rpc;
+pub mod tpu;
+
+pub use bundle::BundleSender;
+pub use fanout::FanoutSender;
+pub use multi_rpc::MultiRpcSender;
+pub use rpc::RpcSender;
+pub use tpu::TpuSender;
+
+#[derive(Debug)]
+pub enum SendError {
+    /// The backend asked us to slow down; the ladder backs off before the next variant.
+    RateLimited(String),
+    /// The transaction itself was refused (preflight, bad signature, ...).
+    Rejected(String),
+    Other(String),
+}
+
+/// A way of getting a signed transaction to the cluster. Blockhashes and
+/// confirmations still go through the RPC client, only submission differs.
+#[async_trait]
+pub trait TxSender: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError>;
+
+    /// Whether this backend can confirm that `signature` landed through it.
+    /// Only backends with their own status API can tell.
+    async fn confirms_landing(&self, _signature: &Signature) -> bool {
+        false
+    }
+
+    /// Told once a sent transaction's on-chain outcome is known.
+    async fn record_outcome(&self, _signature: &Signature, _landed: bool) {}
+}
+
+fn backend(name: &str, client: &Arc<RpcClient>, tipper: &Arc<Keypair>, slots: &Arc<SlotClock>) -> Arc<dyn TxSender> {
+    match name {
+        "rpc" => Arc::new(RpcSender::new(client.clone())),
+        "multi_rpc" => {
+            let urls: Vec<String> = env::var("rpc_urls")
+                .expect("rpc_urls must be set for the multi_rpc sender")
+                .split(',')
+                .map(|u| u.trim().to_string())
+                .filter(|u| !u.is_empty())
+                .collect();
+            Arc::new(MultiRpcSender::new(&urls))
+        }
+        "bundle" => {
+            let block_engine = env::var("block_engine_url").unwrap_or(BLOCK_ENGINE_URL.to_string());
+            let tip = env::var("bundle_tip")
+                .map(|v| v.parse::<f64>().expect("bundle_tip must be a valid f64"))
+                .unwrap_or(0.0001);
+            Arc::new(BundleSender::new(&block_engine, client.clone(), tipper.clone(), sol_to_lamports(tip)))
+        }
+        "tpu" => Arc::new(TpuSender::from_env(LeaderTracker::spawn(client.clone(), slots.clone()))),
+        other => panic!("sender must be rpc, multi_rpc, bundle or tpu, got {}", other),
+    }
+}
+
+/// Builds the sender named by `sender`, a comma-separated list of backends.
+/// More than one backend fans out to all of them. Bundle tips are paid by `tipper`,
+/// and `tpu` picks its leaders from the newer of its own slot poll and `slots`.
+pub fn from_env(client: &Arc<RpcClient>, tipper: &Arc<Keypair>, slots: &Arc<SlotClock>) -> Arc<dyn TxSender> {
+    let names = env::var("sender").unwrap_or("rpc".to_string());
+    let mut backends: Vec<Arc<dyn TxSender>> = names
+        .split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|n| backend(n, client, tipper, slots))
+        .collect();
+
+    match backends.len() {
+        0 => Arc::new(RpcSender::new(client.clone())),
+        1 => backends.remove(0),
+        _ => Arc::new(FanoutSender::new(backends)),
+    }
+}
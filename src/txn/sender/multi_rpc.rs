@@ -0,0 +1,126 @@
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use super::{RpcSender, SendError, TxSender};
+
+/// Submits to several RPC endpoints at once; whichever accepts first wins, and
+/// the rest still get the transaction.
+pub struct MultiRpcSender {
+    endpoints: Vec<Arc<RpcSender>>,
+}
+
+impl MultiRpcSender {
+    pub fn new(urls: &[String]) -> Self {
+        MultiRpcSender {
+            endpoints: urls
+                .iter()
+                .map(|url| Arc::new(RpcSender::new(Arc::new(RpcClient::new(url.clone())))))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl TxSender for MultiRpcSender {
+    fn name(&self) -> &str {
+        "multi_rpc"
+    }
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError> {
+        if self.endpoints.is_empty() {
+            return Err(SendError::Other("no rpc_urls configured".to_string()));
+        }
+
+        let mut sends: FuturesUnordered<_> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let (endpoint, tx) = (endpoint.clone(), tx.clone());
+                async move { endpoint.send(&tx).await }
+            })
+            .collect();
+
+        // a slow endpoint may be the one whose leader forwarding lands the
+        // transaction, so the sends still in flight finish in the background
+        let mut last_error = None;
+        while let Some(result) = sends.next().await {
+            match result {
+                Ok(signature) => {
+                    tokio::spawn(async move { while sends.next().await.is_some() {} });
+                    return Ok(signature);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.expect("at least one endpoint was tried"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Request, Response, Server};
+    use serde_json::{json, Value};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+    use tokio::time::{sleep, Duration};
+
+    use solana_sdk::{hash::Hash, signature::Keypair, signer::Signer, transaction::Transaction};
+
+    fn signed_tx() -> VersionedTransaction {
+        let payer = Keypair::new();
+        Transaction::new_signed_with_payer(&[], Some(&payer.pubkey()), &[&payer], Hash::default()).into()
+    }
+
+    // a local RPC node that accepts every transaction after `delay` and
+    // records the methods it was called with
+    fn spawn_rpc(signature: Signature, delay: Duration) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let make_service = make_service_fn(move |_| {
+            let calls = recorded.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let calls = calls.clone();
+                    async move {
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        let request: Value = serde_json::from_slice(&body).unwrap();
+                        let method = request["method"].as_str().unwrap().to_string();
+                        sleep(delay).await;
+                        let result = match method.as_str() {
+                            "getVersion" => json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+                            _ => json!(signature.to_string()),
+                        };
+                        calls.lock().unwrap().push(method);
+                        let response = json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] });
+                        Ok::<_, Infallible>(Response::new(Body::from(response.to_string())))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+        (addr, calls)
+    }
+
+    #[tokio::test]
+    async fn a_slow_endpoint_still_gets_the_transaction() {
+        let tx = signed_tx();
+        let (fast, _) = spawn_rpc(tx.signatures[0], Duration::ZERO);
+        let (slow, slow_calls) = spawn_rpc(tx.signatures[0], Duration::from_millis(200));
+        let sender = MultiRpcSender::new(&[format!("http://{}", fast), format!("http://{}", slow)]);
+
+        assert_eq!(sender.send(&tx).await.unwrap(), tx.signatures[0]);
+        assert!(slow_calls.lock().unwrap().is_empty());
+
+        sleep(Duration::from_secs(1)).await;
+        assert!(slow_calls.lock().unwrap().contains(&"sendTransaction".to_string()));
+    }
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use solana_client::{client_error::ClientErrorKind, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+
+use super::{SendError, TxSender};
+
+/// Submits through `sendTransaction`, leaving forwarding to the RPC node.
+pub struct RpcSender {
+    client: Arc<RpcClient>,
+}
+
+impl RpcSender {
+    pub fn new(client: Arc<RpcClient>) -> Self {
+        RpcSender { client }
+    }
+}
+
+#[async_trait]
+impl TxSender for RpcSender {
+    fn name(&self) -> &str {
+        "rpc"
+    }
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError> {
+        self.client.send_transaction(tx).await.map_err(|e| match &e.kind {
+            ClientErrorKind::Reqwest(reqwest_err) => SendError::RateLimited(format!("{:?}", reqwest_err)),
+            ClientErrorKind::RpcError(rpc_error) => SendError::Rejected(format!("{:?}", rpc_error)),
+            _ => SendError::Other(format!("{:?}", e)),
+        })
+    }
+}
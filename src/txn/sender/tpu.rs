@@ -0,0 +1,204 @@
+use async_trait::async_trait;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::time::{timeout, Duration};
+
+use solana_connection_cache::{client_connection::ClientStats, connection_cache_stats::ConnectionCacheStats};
+use solana_quic_client::nonblocking::quic_client::{QuicClient, QuicLazyInitializedEndpoint};
+use solana_sdk::{
+    quic::QUIC_MAX_UNSTAKED_CONCURRENT_STREAMS, signature::Signature, transaction::VersionedTransaction,
+};
+
+use super::{SendError, TxSender};
+use crate::txn::leaders::LeaderTracker;
+
+// an unreachable leader would otherwise hold the ladder for the QUIC idle timeout
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Sends straight to the TPU QUIC ports of the next few leaders, skipping
+/// the RPC hop. Connects unstaked, so the leaders treat us like any client.
+pub struct TpuSender {
+    leaders: Option<Arc<LeaderTracker>>,
+    // fixed targets instead of the schedule, e.g. a local QUIC listener
+    static_addrs: Vec<SocketAddr>,
+    fanout: usize,
+    endpoint: Arc<QuicLazyInitializedEndpoint>,
+    clients: Mutex<HashMap<SocketAddr, Arc<QuicClient>>>,
+}
+
+impl TpuSender {
+    fn with_targets(leaders: Option<Arc<LeaderTracker>>, static_addrs: Vec<SocketAddr>, fanout: usize) -> Self {
+        TpuSender {
+            leaders,
+            static_addrs,
+            fanout,
+            endpoint: Arc::new(QuicLazyInitializedEndpoint::default()),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends to the upcoming leaders followed by `leaders`, or to the
+    /// comma-separated `tpu_addrs` when that is set.
+    pub fn from_env(leaders: Arc<LeaderTracker>) -> Self {
+        let fanout = env::var("tpu_fanout")
+            .map(|v| v.parse::<usize>().expect("tpu_fanout must be a valid usize"))
+            .unwrap_or(4);
+
+        match env::var("tpu_addrs") {
+            Ok(addrs) => {
+                let static_addrs = addrs
+                    .split(',')
+                    .map(|a| a.trim().parse::<SocketAddr>().expect("tpu_addrs must be host:port addresses"))
+                    .collect();
+                TpuSender::with_targets(None, static_addrs, fanout)
+            }
+            Err(_) => TpuSender::with_targets(Some(leaders), Vec::new(), fanout),
+        }
+    }
+
+    fn targets(&self) -> Vec<SocketAddr> {
+        match &self.leaders {
+            Some(leaders) => leaders.upcoming_leader_addrs(self.fanout),
+            None => self.static_addrs.clone(),
+        }
+    }
+
+    fn client_for(&self, addr: SocketAddr) -> Arc<QuicClient> {
+        self.clients
+            .lock()
+            .unwrap()
+            .entry(addr)
+            .or_insert_with(|| {
+                Arc::new(QuicClient::new(
+                    self.endpoint.clone(),
+                    addr,
+                    QUIC_MAX_UNSTAKED_CONCURRENT_STREAMS,
+                ))
+            })
+            .clone()
+    }
+}
+
+#[async_trait]
+impl TxSender for TpuSender {
+    fn name(&self) -> &str {
+        "tpu"
+    }
+
+    async fn send(&self, tx: &VersionedTransaction) -> Result<Signature, SendError> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Err(SendError::Other("no leader TPU addresses known yet".to_string()));
+        }
+
+        let wire = Arc::new(bincode::serialize(tx).map_err(|e| SendError::Rejected(e.to_string()))?);
+        let mut sends: FuturesUnordered<_> = targets
+            .into_iter()
+            .map(|addr| {
+                let client = self.client_for(addr);
+                let wire = wire.clone();
+                async move {
+                    let stats = ClientStats::default();
+                    let send = client.send_buffer(wire.as_slice(), &stats, Arc::new(ConnectionCacheStats::default()));
+                    match timeout(SEND_TIMEOUT, send).await {
+                        Ok(Ok(())) => Ok(()),
+                        Ok(Err(e)) => Err(format!("{}: {:?}", addr, e)),
+                        Err(_) => Err(format!("{}: timed out", addr)),
+                    }
+                }
+            })
+            .collect();
+
+        // accepted by any leader is good enough, the rest are redundancy and
+        // finish in the background
+        let mut errors = Vec::new();
+        while let Some(result) = sends.next().await {
+            match result {
+                Ok(()) => {
+                    tokio::spawn(async move { while sends.next().await.is_some() {} });
+                    return Ok(tx.signatures[0]);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(SendError::Other(errors.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::RwLock;
+    use std::time::Instant;
+
+    use solana_sdk::{
+        hash::Hash, net::DEFAULT_TPU_COALESCE, signature::Keypair, signer::Signer, transaction::Transaction,
+    };
+    use solana_streamer::nonblocking::quic::{spawn_server, DEFAULT_MAX_STREAMS_PER_MS};
+    use solana_streamer::packet::PacketBatch;
+    use solana_streamer::streamer::StakedNodes;
+
+    fn signed_tx() -> VersionedTransaction {
+        let payer = Keypair::new();
+        Transaction::new_signed_with_payer(&[], Some(&payer.pubkey()), &[&payer], Hash::default()).into()
+    }
+
+    // a local TPU: a QUIC listener that passes what it receives to `packets`
+    fn spawn_listener() -> (SocketAddr, crossbeam_channel::Receiver<PacketBatch>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let (sender, packets) = crossbeam_channel::unbounded();
+        spawn_server(
+            "tpu_test",
+            socket,
+            &Keypair::new(),
+            addr.ip(),
+            sender,
+            Arc::new(AtomicBool::new(false)),
+            4,
+            Arc::new(RwLock::new(StakedNodes::default())),
+            0,
+            16,
+            DEFAULT_MAX_STREAMS_PER_MS,
+            Duration::from_secs(2),
+            DEFAULT_TPU_COALESCE,
+        )
+        .unwrap();
+        (addr, packets)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn an_unreachable_leader_does_not_hold_up_a_send() {
+        // bound but never answering, like a leader behind a firewall
+        let black_hole = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (listener, packets) = spawn_listener();
+        let sender = TpuSender::with_targets(None, vec![black_hole.local_addr().unwrap(), listener], 2);
+
+        let tx = signed_tx();
+        let started = Instant::now();
+        assert_eq!(sender.send(&tx).await.unwrap(), tx.signatures[0]);
+        assert!(started.elapsed() < SEND_TIMEOUT);
+
+        let batch = tokio::task::spawn_blocking(move || packets.recv_timeout(std::time::Duration::from_secs(5)))
+            .await
+            .unwrap()
+            .unwrap();
+        let wire = bincode::serialize(&tx).unwrap();
+        assert!(batch.iter().any(|packet| packet.data(..) == Some(wire.as_slice())));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn gives_up_on_unreachable_leaders_after_the_timeout() {
+        let black_hole = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sender = TpuSender::with_targets(None, vec![black_hole.local_addr().unwrap()], 1);
+
+        let started = Instant::now();
+        assert!(sender.send(&signed_tx()).await.is_err());
+        assert!(started.elapsed() < SEND_TIMEOUT + Duration::from_secs(1));
+    }
+}
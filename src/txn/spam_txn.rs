@@ -1,50 +1,14 @@
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction,
-    instruction::Instruction, pubkey::Pubkey, signature::Keypair, transaction::Transaction,
-    hash::Hash,
+    instruction::Instruction, signature::Signature, hash::Hash, transaction::VersionedTransaction,
 };
-use std::sync::Arc;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 
-#[derive(Debug)]
-struct TokenInfo {
-    pump_progress: u8,
-    max_holders: u8,
-    market_cap: u64,
-    dev_hold: f64,
-    graduated: bool,
-}
-
-/// Filters tokens based on given criteria
-fn filter_token(token: &TokenInfo) -> bool {
-    token.pump_progress >= 99 &&
-    token.max_holders <= 35 &&
-    token.market_cap >= 4000 &&
-    token.dev_hold <= 1.0 &&
-    token.graduated
-}
-
-/// Fetch token information (stub implementation for demonstration purposes)
-async fn fetch_token_info() -> Vec<TokenInfo> {
-    vec![
-        TokenInfo {
-            pump_progress: 99,
-            max_holders: 30,
-            market_cap: 5000,
-            dev_hold: 0.5,
-            graduated: true,
-        },
-        TokenInfo {
-            pump_progress: 90,
-            max_holders: 40,
-            market_cap: 3000,
-            dev_hold: 1.2,
-            graduated: false,
-        },
-    ]
-}
+use super::executor::{LadderRequest, LadderResult};
+use super::lookup_table::build_transaction;
+use super::sender::{SendError, TxSender};
 
 async fn fetch_blockhash_with_retry(client: &RpcClient, retries: u32) -> Result<Hash, String> {
     let mut attempts = 0;
@@ -66,28 +30,57 @@ async fn fetch_blockhash_with_retry(client: &RpcClient, retries: u32) -> Result<
     Err("Exceeded maximum retries".to_string())
 }
 
-pub async fn spammer(
-    prices_4_spam: Vec<Instruction>,
-    client: &Arc<RpcClient>,
-    PAYER: &Arc<Keypair>,
-    m_pk: &Pubkey,
-    instructions_vec: &Vec<Instruction>,
-) {
+pub async fn spammer(request: LadderRequest<'_>, sender: &dyn TxSender) -> LadderResult {
+    let LadderRequest {
+        prices_4_spam,
+        client,
+        payer,
+        instructions_vec,
+        lookup_table,
+        nonce,
+        ..
+    } = request;
     let max_retries = 3; // Reduced for free RPC testing
     let mut in_trade = false;
+    let mut landed = None;
+    let mut attempted = false;
     let base_delay = Duration::from_millis(1000); // Configurable base delay
 
-    // Fetch and filter tokens
-    let tokens = fetch_token_info().await;
-    let valid_tokens: Vec<_> = tokens.into_iter().filter(filter_token).collect();
+    // Durable nonce: every variant is signed up front over the same nonce,
+    // so there is no blockhash to wait on and at most one of them can land.
+    // Signing is local; the mint-dependent accounts rule out doing it earlier
+    if let Some(nonce) = nonce {
+        let signed: Vec<_> = prices_4_spam
+            .into_iter()
+            .filter_map(|price_ix| {
+                let mut ix_vec = vec![nonce.advance_ix()];
+                ix_vec.extend(instructions_vec.iter().cloned());
+                ix_vec.push(price_ix);
+                match build_transaction(&ix_vec, payer, lookup_table, nonce.blockhash) {
+                    Ok(tx) => Some(tx),
+                    Err(e) => {
+                        eprintln!("Failed to build transaction: {}", e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        for (i, tx) in signed.iter().enumerate() {
+            attempted = true;
+            landed = send_variant(sender, tx, i, base_delay).await.map(|sig| (sig, i));
+            if landed.is_some() {
+                break;
+            }
+        }
 
-    if valid_tokens.is_empty() {
-        println!("No tokens passed the filters.");
-        return;
+        println!("Spammer function exiting. Monitoring halted due to active trade or completion.");
+        return LadderResult {
+            accepted: landed,
+            attempted,
+        };
     }
 
-    println!("Filtered tokens: {:?}", valid_tokens);
-
     for (i, price_ix) in prices_4_spam.into_iter().enumerate() {
         if in_trade {
             println!("Already in a trade, stopping further monitoring.");
@@ -104,46 +97,58 @@ pub async fn spammer(
         };
 
         // Prepare transaction
-        let mut ix_vec = instructions_vec.clone();
+        let mut ix_vec = instructions_vec.to_vec();
         ix_vec.push(price_ix);
 
-        let tx = Transaction::new_signed_with_payer(
-            &ix_vec,
-            Some(m_pk),
-            &[PAYER],
-            recent_blockhash,
-        );
-
-        match client.send_transaction(&tx).await {
-            Ok(signature) => {
-                println!("Transaction succeeded with signature: {}", signature);
-                in_trade = true; // Stop monitoring once a trade is initiated
-                break;
-            }
+        let tx = match build_transaction(&ix_vec, payer, lookup_table, recent_blockhash) {
+            Ok(tx) => tx,
             Err(e) => {
-                match &e.kind {
-                    solana_client::client_error::ClientErrorKind::Reqwest(reqwest_err) => {
-                        eprintln!("Rate-limited: {:?}", reqwest_err);
-                        let delay = base_delay * (i as u32 + 1); // Exponential backoff
-                        eprintln!("Retrying after {:?} delay...", delay);
-                        sleep(delay).await;
-                    }
-                    solana_client::client_error::ClientErrorKind::RpcError(rpc_error) => {
-                        eprintln!("RPC Error: {:?}", rpc_error);
-                    }
-                    _ => eprintln!("Transaction failed: {:?}", e),
-                }
+                eprintln!("Failed to build transaction: {}", e);
+                continue;
             }
-        }
+        };
 
-        // Add a delay to avoid rate-limiting
-        sleep(base_delay).await;
+        attempted = true;
+        landed = send_variant(sender, &tx, i, base_delay).await.map(|sig| (sig, i));
+        if landed.is_some() {
+            in_trade = true; // Stop monitoring once a trade is initiated
+        }
     }
 
     println!("Spammer function exiting. Monitoring halted due to active trade or completion.");
+    LadderResult {
+        accepted: landed,
+        attempted,
+    }
 }
 
 
+async fn send_variant(
+    sender: &dyn TxSender,
+    tx: &VersionedTransaction,
+    i: usize,
+    base_delay: Duration,
+) -> Option<Signature> {
+    match sender.send(tx).await {
+        Ok(signature) => {
+            println!("Transaction succeeded with signature: {}", signature);
+            return Some(signature);
+        }
+        Err(SendError::RateLimited(e)) => {
+            eprintln!("Rate-limited: {}", e);
+            let delay = base_delay * (i as u32 + 1); // Exponential backoff
+            eprintln!("Retrying after {:?} delay...", delay);
+            sleep(delay).await;
+        }
+        Err(SendError::Rejected(e)) => eprintln!("RPC Error: {}", e),
+        Err(SendError::Other(e)) => eprintln!("Transaction failed via {}: {}", sender.name(), e),
+    }
+
+    // Add a delay to avoid rate-limiting
+    sleep(base_delay).await;
+    None
+}
+
 /// Generate instructions for fees
 pub async fn array_of_fees(spam_amount: u64, spam_price: u64) -> Vec<Instruction> {
     let mut instructions = Vec::new();
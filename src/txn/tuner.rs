@@ -0,0 +1,220 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction};
+
+// outcomes seen on the current ladder before it is moved again
+const MIN_OUTCOMES: usize = 5;
+const RAISE: f64 = 1.25;
+const DECAY: f64 = 0.95;
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct TunerConfig {
+    /// Share of buys that should land within `target_slot_delay`.
+    pub target_rate: f64,
+    /// A buy landing later than this many slots after the create counts as missed.
+    pub target_slot_delay: u64,
+    /// Most the top variant may pay in priority fees, in lamports.
+    pub fee_budget_lamports: u64,
+    /// Outcomes the landing rate is measured over.
+    pub window: usize,
+    pub state_path: PathBuf,
+}
+
+impl TunerConfig {
+    /// `None` unless `fee_tuning` is enabled.
+    pub fn from_env() -> Option<Self> {
+        let enabled = env::var("fee_tuning")
+            .map(|v| v.parse::<bool>().expect("fee_tuning must be true or false"))
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let target_rate = env::var("fee_target_landing")
+            .map(|v| v.parse::<f64>().expect("fee_target_landing must be a valid f64"))
+            .unwrap_or(0.8);
+
+        let target_slot_delay = env::var("fee_target_slot_delay")
+            .map(|v| v.parse::<u64>().expect("fee_target_slot_delay must be a valid u64"))
+            .unwrap_or(2);
+
+        let fee_budget_lamports = env::var("fee_cap_lamports")
+            .map(|v| v.parse::<u64>().expect("fee_cap_lamports must be a valid u64"))
+            .unwrap_or(1_000_000);
+
+        let window = env::var("fee_tuning_window")
+            .map(|v| v.parse::<usize>().expect("fee_tuning_window must be a valid usize"))
+            .unwrap_or(20);
+
+        let state_path = PathBuf::from(env::var("fee_tuning_state").unwrap_or("fee_tuner.json".to_string()));
+
+        Some(TunerConfig {
+            target_rate,
+            target_slot_delay,
+            fee_budget_lamports,
+            window,
+            state_path,
+        })
+    }
+}
+
+/// How one buy ladder did.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LadderOutcome {
+    /// Index of the variant that was accepted, `None` if none was.
+    pub tier: Option<usize>,
+    /// Its price in micro-lamports per CU.
+    pub price: Option<u64>,
+    /// Slots between the create and our landing, `None` if it did not land.
+    pub slot_delay: Option<u64>,
+}
+
+/// The learned ladder: `steps` prices spread geometrically from `floor` to
+/// `ceiling` micro-lamports per CU. Written to disk after every change.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct TunerState {
+    floor: u64,
+    ceiling: u64,
+    outcomes: VecDeque<LadderOutcome>,
+    // outcomes recorded since floor or ceiling last moved
+    #[serde(default)]
+    since_change: usize,
+}
+
+pub struct FeeTuner {
+    config: TunerConfig,
+    steps: usize,
+    max_price: u64,
+    state: Mutex<TunerState>,
+}
+
+impl FeeTuner {
+    /// Resumes from `state_path` if it exists, otherwise starts from the
+    /// static ladder's range.
+    pub fn load(config: TunerConfig, unit_limit: u32, static_ladder: &[u64]) -> Self {
+        let max_price = (config.fee_budget_lamports as u128 * 1_000_000 / unit_limit.max(1) as u128) as u64;
+
+        let state = match fs::read_to_string(&config.state_path).map(|s| serde_json::from_str::<TunerState>(&s)) {
+            Ok(Ok(state)) => {
+                println!(
+                    "Resumed fee tuning from {}: {}..{} micro-lamports",
+                    config.state_path.display(),
+                    state.floor,
+                    state.ceiling
+                );
+                state
+            }
+            _ => {
+                let floor = static_ladder.iter().copied().min().unwrap_or(10_000).max(1);
+                let ceiling = static_ladder.iter().copied().max().unwrap_or(floor).max(floor * 2);
+                TunerState {
+                    floor: floor.min(max_price),
+                    ceiling: ceiling.min(max_price),
+                    outcomes: VecDeque::new(),
+                    since_change: 0,
+                }
+            }
+        };
+
+        FeeTuner {
+            config,
+            steps: static_ladder.len().max(1),
+            max_price,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn ladder(&self) -> Vec<Instruction> {
+        let state = self.state.lock().unwrap();
+        ladder_prices(state.floor, state.ceiling, self.steps)
+            .into_iter()
+            .map(ComputeBudgetInstruction::set_compute_unit_price)
+            .collect()
+    }
+
+    pub fn record(&self, outcome: LadderOutcome) {
+        let mut state = self.state.lock().unwrap();
+        state.outcomes.push_back(outcome);
+        while state.outcomes.len() > self.config.window {
+            state.outcomes.pop_front();
+        }
+        state.since_change += 1;
+        if state.since_change >= MIN_OUTCOMES {
+            self.adjust(&mut state);
+        }
+
+        if let Err(e) = save(&self.config.state_path, &state) {
+            eprintln!("Failed to save fee tuning state: {:?}", e);
+        }
+    }
+
+    fn adjust(&self, state: &mut TunerState) {
+        let in_time = |o: &LadderOutcome| o.slot_delay.is_some_and(|d| d <= self.config.target_slot_delay);
+        let rate = state.outcomes.iter().filter(|o| in_time(o)).count() as f64 / state.outcomes.len() as f64;
+        let top_tier = self.steps - 1;
+        let (floor, ceiling) = (state.floor, state.ceiling);
+
+        if rate < self.config.target_rate {
+            // missing: move the whole ladder up
+            state.floor = ((floor as f64 * RAISE) as u64).max(floor + 1);
+            state.ceiling = ((ceiling as f64 * RAISE) as u64).max(ceiling + 1);
+        } else {
+            // landing enough: drift the floor down, and the ceiling too
+            // unless the top of the ladder is what is winning
+            state.floor = (floor as f64 * DECAY) as u64;
+            let top_wins = state.outcomes.iter().any(|o| in_time(o) && o.tier == Some(top_tier));
+            if !top_wins {
+                state.ceiling = (ceiling as f64 * DECAY) as u64;
+            }
+        }
+        state.ceiling = state.ceiling.min(self.max_price);
+        state.floor = state.floor.max(1).min(state.ceiling);
+
+        if (state.floor, state.ceiling) != (floor, ceiling) {
+            state.since_change = 0;
+            println!(
+                "{}:: landing {:.0}% over {} buys, fee ladder now {}..{} micro-lamports",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                rate * 100.0,
+                state.outcomes.len(),
+                state.floor,
+                state.ceiling
+            );
+        }
+    }
+}
+
+fn save(path: &PathBuf, state: &TunerState) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(state).map_err(std::io::Error::other)?;
+    // write then rename, so a crash never leaves half a file behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+// geometric spacing: the gap between variants grows with the price, and
+// shrinks as floor and ceiling close in
+fn ladder_prices(floor: u64, ceiling: u64, steps: usize) -> Vec<u64> {
+    if steps <= 1 || ceiling <= floor {
+        return vec![floor];
+    }
+
+    let ratio = (ceiling as f64 / floor as f64).powf(1.0 / (steps - 1) as f64);
+    let mut prices: Vec<u64> = Vec::new();
+    for i in 0..steps {
+        let mut price = (floor as f64 * ratio.powi(i as i32)).round() as u64;
+        // identical prices would sign identical transactions
+        if let Some(last) = prices.last() {
+            price = price.max(last + 1);
+        }
+        prices.push(price);
+    }
+    prices
+}
@@ -0,0 +1,251 @@
+use solana_sdk::pubkey::Pubkey;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+
+use super::layouts::TradeEvent;
+use crate::strategy::{CreateInfo, Fill, Intent};
+
+/// Everything that moves between the pipeline stages.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A create decoded from the log stream, before any filter.
+    Create(CreateInfo),
+    /// A create that passed the filters and is shown to the strategies.
+    Candidate(CreateInfo),
+    Rejected { mint: Pubkey, reason: String },
+    /// `held` when a strategy had an open position in the mint at publish time.
+    Trade { trade: TradeEvent, held: bool },
+    /// Admitted by the strategy runner, ready to execute.
+    Intent { strategy: usize, intent: Intent },
+    /// A held mint looks rugged, the strategy sells on the emergency ladder.
+    EmergencySell { strategy: usize, mint: Pubkey },
+    Fill { strategy: usize, fill: Fill },
+    /// A sell was not accepted, did not land or could not be confirmed; the position is still held.
+    SellFailed { strategy: usize, mint: Pubkey },
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Create(_) => "create",
+            Event::Candidate(_) => "candidate",
+            Event::Rejected { .. } => "rejected",
+            Event::Trade { .. } => "trade",
+            Event::Intent { .. } => "intent",
+            Event::EmergencySell { .. } => "emergency_sell",
+            Event::Fill { .. } => "fill",
+            Event::SellFailed { .. } => "sell_failed",
+        }
+    }
+
+    /// Decisions and their outcomes, which no subscriber may miss, and the
+    /// trades of held mints that exits and rug checks are decided on.
+    pub fn is_lossless(&self) -> bool {
+        matches!(
+            self,
+            Event::Trade { held: true, .. }
+                | Event::Intent { .. }
+                | Event::EmergencySell { .. }
+                | Event::Fill { .. }
+                | Event::SellFailed { .. }
+        )
+    }
+}
+
+/// Typed bus between ingestion and the stages consuming it. Every
+/// subscriber sees every event, in the order it was published. Events
+/// published by different stages may interleave.
+///
+/// Each subscriber has its own queue. Intents, emergency sells, fills,
+/// failed sells and trades of held mints are always delivered; observational
+/// events are dropped for a subscriber that is `capacity` of them behind.
+///
+/// The bus counts events not yet handled and tasks the handlers started,
+/// so replay can wait for the pipeline to go quiet before the next message.
+pub struct EventBus {
+    subscribers: Mutex<Vec<Subscriber>>,
+    capacity: usize,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+struct Subscriber {
+    sender: mpsc::UnboundedSender<Event>,
+    backlog: Arc<Backlog>,
+}
+
+#[derive(Default)]
+struct Backlog {
+    // observational events queued and not yet handled
+    queued: AtomicUsize,
+    // dropped since the subscriber last handled an event
+    dropped: AtomicUsize,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(EventBus {
+            subscribers: Mutex::new(Vec::new()),
+            capacity: capacity.max(1),
+            in_flight: AtomicUsize::new(0),
+            idle: Notify::new(),
+        })
+    }
+
+    pub fn publish(&self, event: Event) {
+        let lossless = event.is_lossless();
+        for subscriber in self.subscribers.lock().unwrap().iter() {
+            let backlog = &subscriber.backlog;
+            if !lossless {
+                if backlog.queued.load(Ordering::SeqCst) >= self.capacity {
+                    backlog.dropped.fetch_add(1, Ordering::SeqCst);
+                    continue;
+                }
+                backlog.queued.fetch_add(1, Ordering::SeqCst);
+            }
+            self.in_flight.fetch_add(1, Ordering::SeqCst);
+            if subscriber.sender.send(event.clone()).is_err() {
+                if !lossless {
+                    backlog.queued.fetch_sub(1, Ordering::SeqCst);
+                }
+                self.done(1);
+            }
+        }
+    }
+
+    /// Runs `handler` on every event from now on. Tasks it returns count as
+    /// in flight until they finish. Subscribe before the first publish or
+    /// the events before it are missed.
+    pub fn subscribe<F>(self: &Arc<Self>, name: &'static str, mut handler: F) -> JoinHandle<()>
+    where
+        F: FnMut(Event) -> Vec<JoinHandle<()>> + Send + 'static,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let backlog = Arc::new(Backlog::default());
+        self.subscribers.lock().unwrap().push(Subscriber {
+            sender,
+            backlog: backlog.clone(),
+        });
+        let bus = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                if !event.is_lossless() {
+                    backlog.queued.fetch_sub(1, Ordering::SeqCst);
+                }
+                let dropped = backlog.dropped.swap(0, Ordering::SeqCst);
+                if dropped > 0 {
+                    eprintln!("{} fell behind the event bus, {} events dropped", name, dropped);
+                }
+                for handle in handler(event) {
+                    bus.track(handle);
+                }
+                bus.done(1);
+            }
+        })
+    }
+
+    /// Counts a task as in flight until it finishes.
+    pub fn track(self: &Arc<Self>, handle: JoinHandle<()>) {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let bus = self.clone();
+        tokio::spawn(async move {
+            let _ = handle.await;
+            bus.done(1);
+        });
+    }
+
+    /// Resolves once every published event was handled and every tracked task finished.
+    pub async fn settled(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn done(&self, count: usize) {
+        if self.in_flight.fetch_sub(count, Ordering::SeqCst) == count {
+            self.idle.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{Fill, Side};
+
+    fn trade(held: bool) -> Event {
+        let trade = TradeEvent {
+            mint: Pubkey::new_unique(),
+            sol_amount: 0,
+            token_amount: 0,
+            is_buy: true,
+            user: Pubkey::new_unique(),
+            timestamp: 0,
+            virtual_sol_reserves: 0,
+            virtual_token_reserves: 0,
+        };
+        Event::Trade { trade, held }
+    }
+
+    fn fill() -> Event {
+        Event::Fill {
+            strategy: 0,
+            fill: Fill {
+                mint: Pubkey::new_unique(),
+                side: Side::Buy,
+                token_amount: 0,
+                lamports: 0,
+                time_ms: 0,
+                slot: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn a_subscriber_behind_skips_trades_but_never_fills() {
+        let bus = EventBus::new(1);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let kinds = seen.clone();
+        bus.subscribe("test", move |event| {
+            kinds.lock().unwrap().push(event.kind());
+            Vec::new()
+        });
+
+        // nothing runs before the first await, so everything queues up
+        for _ in 0..3 {
+            bus.publish(trade(false));
+            bus.publish(fill());
+        }
+        bus.settled().await;
+
+        assert_eq!(*seen.lock().unwrap(), vec!["trade", "fill", "fill", "fill"]);
+    }
+
+    #[tokio::test]
+    async fn a_subscriber_behind_still_sees_trades_of_held_mints() {
+        let bus = EventBus::new(1);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let held = seen.clone();
+        bus.subscribe("test", move |event| {
+            if let Event::Trade { held: h, .. } = event {
+                held.lock().unwrap().push(h);
+            }
+            Vec::new()
+        });
+
+        // the first trade fills the queue, only held ones get past it
+        for _ in 0..3 {
+            bus.publish(trade(false));
+            bus.publish(trade(true));
+        }
+        bus.settled().await;
+
+        assert_eq!(*seen.lock().unwrap(), vec![false, true, true, true]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+
+use super::layouts::TradeEvent;
+
+/// Candle widths in seconds: 1s, 5s, 1m.
+pub const CANDLE_INTERVALS: [i64; 3] = [1, 5, 60];
+
+#[derive(Clone, Debug)]
+pub struct CandleConfig {
+    /// Closed candles kept per mint and interval.
+    pub history: usize,
+    /// Mints tracked at once; the least recently traded is evicted first.
+    pub max_mints: usize,
+    /// Closed candles are appended here as JSON lines when set.
+    pub flush_dir: Option<PathBuf>,
+}
+
+impl CandleConfig {
+    pub fn from_env() -> Self {
+        let history = env::var("candle_history")
+            .map(|v| v.parse::<usize>().expect("candle_history must be a valid usize"))
+            .unwrap_or(300);
+
+        let max_mints = env::var("candle_max_mints")
+            .map(|v| v.parse::<usize>().expect("candle_max_mints must be a valid usize"))
+            .unwrap_or(2_000);
+
+        let flush_dir = env::var("candle_dir").ok().map(PathBuf::from);
+
+        CandleConfig {
+            history,
+            max_mints,
+            flush_dir,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Candle {
+    pub interval: i64,
+    pub start: i64,
+    // prices are lamports per raw token unit, from the virtual reserves
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume_sol: u64,
+    pub buys: u32,
+    pub sells: u32,
+    traders: HashSet<Pubkey>,
+}
+
+impl Candle {
+    fn new(interval: i64, start: i64, price: f64) -> Self {
+        Candle {
+            interval,
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume_sol: 0,
+            buys: 0,
+            sells: 0,
+            traders: HashSet::new(),
+        }
+    }
+
+    fn apply(&mut self, price: f64, trade_event: &TradeEvent) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume_sol += trade_event.get_sol_amount();
+        if trade_event.is_buy_bool() {
+            self.buys += 1;
+        } else {
+            self.sells += 1;
+        }
+        self.traders.insert(trade_event.user);
+    }
+
+    pub fn unique_traders(&self) -> usize {
+        self.traders.len()
+    }
+}
+
+struct MintCandles {
+    last_update: i64,
+    // one (current, closed history) pair per entry of CANDLE_INTERVALS
+    current: [Option<Candle>; 3],
+    closed: [VecDeque<Candle>; 3],
+}
+
+struct CandleState {
+    mints: HashMap<Pubkey, MintCandles>,
+    pending_flush: Vec<(Pubkey, Candle)>,
+}
+
+/// Incrementally builds per-mint OHLCV candles from decoded TradeEvents.
+pub struct CandleStore {
+    config: CandleConfig,
+    state: Mutex<CandleState>,
+}
+
+fn interval_index(interval: i64) -> Option<usize> {
+    CANDLE_INTERVALS.iter().position(|i| *i == interval)
+}
+
+impl CandleStore {
+    pub fn new(config: CandleConfig) -> Self {
+        CandleStore {
+            config,
+            state: Mutex::new(CandleState {
+                mints: HashMap::new(),
+                pending_flush: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn on_trade(&self, trade_event: &TradeEvent) {
+        let v_tokens = trade_event.get_virtual_token_reserves();
+        if v_tokens == 0 {
+            return;
+        }
+        let price = trade_event.get_virtual_sol_reserves() as f64 / v_tokens as f64;
+        let ts = trade_event.get_timestamp();
+
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+
+        if !state.mints.contains_key(&trade_event.mint) && state.mints.len() >= self.config.max_mints
+        {
+            let oldest = state
+                .mints
+                .iter()
+                .min_by_key(|(_, m)| m.last_update)
+                .map(|(mint, _)| *mint);
+            if let Some(oldest) = oldest {
+                state.mints.remove(&oldest);
+            }
+        }
+
+        let mint_candles = state
+            .mints
+            .entry(trade_event.mint)
+            .or_insert_with(|| MintCandles {
+                last_update: ts,
+                current: [None, None, None],
+                closed: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            });
+        mint_candles.last_update = mint_candles.last_update.max(ts);
+
+        for (idx, interval) in CANDLE_INTERVALS.iter().enumerate() {
+            let start = ts - ts.rem_euclid(*interval);
+
+            // late trades are folded into the open candle rather than rewriting history
+            let rolls_over = match &mint_candles.current[idx] {
+                Some(candle) => start > candle.start,
+                None => true,
+            };
+
+            if rolls_over {
+                if let Some(finished) = mint_candles.current[idx].take() {
+                    if self.config.flush_dir.is_some() {
+                        state.pending_flush.push((trade_event.mint, finished.clone()));
+                    }
+                    let closed = &mut mint_candles.closed[idx];
+                    closed.push_back(finished);
+                    while closed.len() > self.config.history {
+                        closed.pop_front();
+                    }
+                }
+                mint_candles.current[idx] = Some(Candle::new(*interval, start, price));
+            }
+
+            if let Some(candle) = mint_candles.current[idx].as_mut() {
+                candle.apply(price, trade_event);
+            }
+        }
+    }
+
+    /// Up to `count` most recent candles, oldest first, including the open one.
+    pub fn recent(&self, mint: &Pubkey, interval: i64, count: usize) -> Vec<Candle> {
+        let idx = match interval_index(interval) {
+            Some(idx) => idx,
+            None => return Vec::new(),
+        };
+        let state = self.state.lock().unwrap();
+        let mint_candles = match state.mints.get(mint) {
+            Some(mint_candles) => mint_candles,
+            None => return Vec::new(),
+        };
+
+        let mut candles: Vec<Candle> = mint_candles.closed[idx]
+            .iter()
+            .chain(mint_candles.current[idx].iter())
+            .cloned()
+            .collect();
+        let skip = candles.len().saturating_sub(count);
+        candles.drain(..skip);
+        candles
+    }
+
+    /// Close-over-open price change across the last `count` candles, e.g. 0.2 = +20%.
+    pub fn momentum(&self, mint: &Pubkey, interval: i64, count: usize) -> Option<f64> {
+        let candles = self.recent(mint, interval, count);
+        let first = candles.first()?;
+        let last = candles.last()?;
+        if first.open <= 0.0 {
+            return None;
+        }
+        Some(last.close / first.open - 1.0)
+    }
+
+    /// Appends closed candles to `candle_dir/candles-<date>.jsonl`.
+    pub fn flush(&self) -> std::io::Result<usize> {
+        let dir = match &self.config.flush_dir {
+            Some(dir) => dir,
+            None => return Ok(0),
+        };
+
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending_flush);
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("candles-{}.jsonl", Local::now().format("%Y-%m-%d")));
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        for (mint, candle) in &pending {
+            let line = json!({
+                "mint": mint.to_string(),
+                "interval": candle.interval,
+                "start": candle.start,
+                "open": candle.open,
+                "high": candle.high,
+                "low": candle.low,
+                "close": candle.close,
+                "volume_sol": candle.volume_sol,
+                "buys": candle.buys,
+                "sells": candle.sells,
+                "unique_traders": candle.unique_traders(),
+            });
+            writeln!(file, "{}", line)?;
+        }
+
+        Ok(pending.len())
+    }
+}
@@ -1,22 +1,146 @@
 use chrono::Local;
+use serde_json::Value;
 use std::sync::Arc;
-use std::time::Duration;
 
 use solana_client::nonblocking::rpc_client::RpcClient;
 
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, instruction::Instruction, pubkey::Pubkey, signer::Signer,
+};
 use spl_associated_token_account;
 use spl_token::instruction::close_account;
 
+use super::bus::{Event, EventBus};
+use super::candles::CandleStore;
 use super::constants::*;
-use super::create_ix::{create_sell_ix, get_buy_ix};
+use super::create_ix::{bonding_curve_ata, create_sell_ix, get_buy_ix};
+use super::pf_events::{decode_pump_events, decode_trade_events, PumpEvent};
 use super::pf_price::*;
+use super::positions::{MintState, Position, PositionBook};
+use super::risk::RiskManager;
+
+use crate::strategy::runner::StrategyRunner;
+use crate::strategy::{CreateInfo, Fill, Side};
+use crate::txn::executor::{Executor, LadderRequest};
+use crate::txn::fees::{compute_unit_price, FeeLadders};
+use crate::txn::tuner::LadderOutcome;
+use crate::txn::nonce::NoncePool;
+use crate::wallet::pool::WalletPool;
+
+use super::holders::HolderAnalyser;
+use super::insiders::InsiderDetector;
+use super::metadata::MetadataFilter;
+use super::rug_watch::{find_creator_funded_wallets, RugWatch};
+use super::slots::SlotClock;
+use super::snipers::SniperBoard;
+use super::token_balance::{ata_balance, ata_closed, token_delta};
+
+/// Everything ingestion and the pipeline stages need, shared between the
+/// live WebSocket loop and replay.
+pub struct TradeContext {
+    pub client: Arc<RpcClient>,
+    pub wallets: WalletPool,
+    pub slippage: f64,
+    pub unit_limit_ix: Instruction,
+    pub fees: FeeLadders,
+    /// Table with the static pump.fun accounts; transactions are v0 when set.
+    pub lookup_table: Option<AddressLookupTableAccount>,
+    /// Durable nonces for the ladders, blockhashes are used when `None` or exhausted.
+    pub nonces: Option<Arc<NoncePool>>,
+    pub rug_watch: Arc<RugWatch>,
+    pub candle_store: Arc<CandleStore>,
+    pub strategies: StrategyRunner,
+    pub positions: PositionBook,
+    pub risk: RiskManager,
+    pub executor: Executor,
+    pub slots: Arc<SlotClock>,
+    pub snipers: Arc<SniperBoard>,
+    pub insiders: Arc<InsiderDetector>,
+    pub metadata: Arc<MetadataFilter>,
+    pub holders: Arc<HolderAnalyser>,
+    pub bus: Arc<EventBus>,
+}
 
-use crate::txn::spam_txn::spammer;
+/// Parses one raw `logsNotification`, updates the shared state the stages
+/// read (slots, curves, candles, early buyers) and publishes the decoded
+/// creates and trades on the bus. Decisions are left to the pipeline stages.
+pub fn handle_message(text: &str, ctx: &Arc<TradeContext>) {
+    let parsed = match serde_json::from_str::<Value>(text) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("Failed to parse JSON: {:?}", e);
+            return;
+        }
+    };
+
+    // Extract logs
+    let logs: Vec<String> = match parsed["params"]["result"]["value"]["logs"].as_array() {
+        Some(jsonified) => jsonified
+            .iter()
+            .map(|v| v.as_str().unwrap_or_default().to_string())
+            .collect(),
+        None => return,
+    };
+
+    // the context slot is when the RPC saw the transaction, i.e. its slot
+    let slot = parsed["params"]["result"]["context"]["slot"].as_u64().unwrap_or(0);
+    ctx.slots.observe(slot);
+    let signature = parsed["params"]["result"]["value"]["signature"].as_str().unwrap_or_default();
+
+    if let Some(mut create) = parse_create(&logs) {
+        create.slot = slot;
+        println!(
+            "{}:: user: {:?} \nmint: {:?} \nslot: {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            create.creator,
+            create.mint,
+            create.slot
+        );
+        ctx.positions.track_mint(
+            create.mint,
+            MintState {
+                bonding_curve: create.bonding_curve,
+                creator: create.creator,
+                virtual_sol_reserves: create.virtual_sol_reserves,
+                virtual_token_reserves: create.virtual_token_reserves,
+                create_slot: create.slot,
+            },
+        );
+        ctx.snipers.on_create(create.mint, create.creator, create.slot);
+        ctx.bus.publish(Event::Create(create));
+    }
 
-use super::layouts::TradeEvent;
+    for trade_event in decode_trade_events(&logs) {
+        ctx.positions.update_curve(
+            &trade_event.mint,
+            trade_event.get_virtual_sol_reserves(),
+            trade_event.get_virtual_token_reserves(),
+        );
+        ctx.candle_store.on_trade(&trade_event);
+        // the fee is only in the transaction itself, fetch it off the hot path
+        if ctx.snipers.on_trade(&trade_event, slot, signature) {
+            if let Ok(sig) = signature.parse() {
+                ctx.snipers.request_priority_fee(trade_event.mint, sig);
+            }
+        }
+        // a held mint's trades must reach the strategies and the rug check
+        let held = !ctx.positions.holders_of(&trade_event.mint).is_empty();
+        ctx.bus.publish(Event::Trade {
+            trade: trade_event,
+            held,
+        });
+    }
+}
 
-async fn valid_logs(logs: &Vec<String>) -> bool {
+/// Fires `on_timer` for every strategy at the executor's notion of now and
+/// publishes the resulting intents.
+pub fn run_timers(ctx: &Arc<TradeContext>) {
+    for (strategy, intent) in ctx.strategies.on_timer(ctx.executor.now_ms(), &ctx.candle_store) {
+        ctx.bus.publish(Event::Intent { strategy, intent });
+    }
+}
+
+fn valid_logs(logs: &Vec<String>) -> bool {
     let mut a = false;
     let mut b = false;
     for msg in logs {
@@ -35,210 +159,587 @@ async fn valid_logs(logs: &Vec<String>) -> bool {
     return false;
 }
 
-pub async fn process_logs(
-    logs: &Vec<String>,
-    client: Arc<RpcClient>,
-    PAYER: Arc<Keypair>,
-    investment_lamported: f64,
-    slippage: f64,
-    adjusted_investment_for_fees: f64,
-    unit_limit_ix: Instruction,
-    prices_4_spam: Vec<Instruction>,
-    m_pk: &Pubkey,
-) {
-    let mut mint = Pubkey::default();
-    let mut bc_pk = Pubkey::default();
-    let mut user = Pubkey::default();
-    let mut logs_counted = 0;
-    let mut virtual_sol_reserves = 0;
-    let mut virtual_token_reserves = 0;
-
-    if valid_logs(logs).await {
-        for log in logs {
-            if log.contains("Program data:") {
-                let log_data = log.replace("Program data: ", "");
-                let log_decoded = base64::decode(&log_data).expect("Failed to decode base64");
-
-                let len_data = log_decoded.len();
-
-                if len_data >= 180 && logs_counted == 0 {
-                    logs_counted = logs_counted + 1;
-
-                    // Convert slices to arrays safely
-                    if let Some(user_bytes) = log_decoded
-                        .get(len_data - 32..len_data)
-                        .and_then(|slice| slice.try_into().ok())
-                    {
-                        user = Pubkey::new_from_array(user_bytes);
-                        if let Some(bonding_curve_bytes) = log_decoded
-                            .get(len_data - 64..len_data - 32)
-                            .and_then(|slice| slice.try_into().ok())
-                        {
-                            bc_pk = Pubkey::new_from_array(bonding_curve_bytes);
-                            if let Some(mint_bytes) = log_decoded
-                                .get(len_data - 96..len_data - 64)
-                                .and_then(|slice| slice.try_into().ok())
-                            {
-                                let mint_temp = Pubkey::new_from_array(mint_bytes);
-                                if mint_temp.to_string().contains("pump") {
-                                    mint = mint_temp;
-                                }
-                            }
-                        }
-                    }
-                } else if logs_counted > 0 {
-                    //get bonding curve data from the create txn directly...
-                    let trade_event = TradeEvent::decode_trade_event(&log_decoded[8..]);
-                    virtual_sol_reserves = trade_event.get_virtual_sol_reserves();
-                    virtual_token_reserves = trade_event.get_virtual_token_reserves();
+/// Pulls the new mint and the curve state after the creator's buy out of a create
+/// transaction. `None` if the logs don't hold a pump create followed by a trade on it.
+pub fn parse_create(logs: &Vec<String>) -> Option<CreateInfo> {
+    if !valid_logs(logs) {
+        return None;
+    }
+
+    let mut events = decode_pump_events(logs).into_iter();
+    let create_event = events.find_map(|event| match event {
+        PumpEvent::Create(create_event) if create_event.mint.to_string().contains("pump") => {
+            Some(create_event)
+        }
+        _ => None,
+    })?;
+    // the creator's own buy follows the create in the same transaction
+    let trade_event = events.find_map(|event| match event {
+        PumpEvent::Trade(trade_event) if trade_event.mint == create_event.mint => Some(trade_event),
+        _ => None,
+    })?;
+
+    let virtual_sol_reserves = trade_event.get_virtual_sol_reserves();
+    let virtual_token_reserves = trade_event.get_virtual_token_reserves();
+    if create_event.user == Pubkey::default()
+        || create_event.bonding_curve == Pubkey::default()
+        || virtual_sol_reserves == 0
+        || virtual_token_reserves == 0
+    {
+        return None;
+    }
+
+    Some(CreateInfo {
+        mint: create_event.mint,
+        bonding_curve: create_event.bonding_curve,
+        creator: create_event.user,
+        virtual_sol_reserves,
+        virtual_token_reserves,
+        slot: 0,
+        name: create_event.name,
+        symbol: create_event.symbol,
+        uri: create_event.uri,
+    })
+}
+
+pub async fn execute_buy(ctx: &Arc<TradeContext>, strategy: usize, mint: Pubkey, lamports: u64) {
+    let client = &ctx.client;
+    let rug_watch = &ctx.rug_watch;
+
+    // a Cancel may have arrived between the decision and now
+    if ctx.strategies.take_pending(strategy, &mint).is_none() {
+        return;
+    }
+    let state = match ctx.positions.mint_state(&mint) {
+        Some(state) => state,
+        None => {
+            ctx.strategies.release(strategy, lamports);
+            return;
+        }
+    };
+    // past the strategy's lag the early-entry edge is gone
+    let limits = ctx.strategies.limits(strategy);
+    if let (Some(max_lag), Some(lag)) = (limits.max_slot_lag, ctx.slots.lag(state.create_slot)) {
+        if lag > max_lag {
+            println!(
+                "{}:: {} is {} slots past its create (max {}), not buying",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                mint,
+                lag,
+                max_lag
+            );
+            ctx.strategies.release(strategy, lamports);
+            return;
+        }
+    }
+    // only counts the create-slot buys seen so far
+    if let Some(max_snipers) = limits.max_block0_snipers {
+        let snipers = ctx.snipers.snipers_in_block0(&mint);
+        if snipers > max_snipers {
+            println!(
+                "{}:: {} had {} snipers in its create slot (max {}), not buying",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                mint,
+                snipers,
+                max_snipers
+            );
+            ctx.strategies.release(strategy, lamports);
+            return;
+        }
+    }
+    if ctx.metadata.fetches() {
+        // still in flight after the timeout counts as unreadable
+        let metadata = ctx.metadata.wait_for(&mint).await.flatten();
+        if let Err(reason) = ctx.metadata.check_metadata(metadata.as_ref()) {
+            println!(
+                "{}:: {} metadata rejected: {}, not buying",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                mint,
+                reason
+            );
+            ctx.strategies.release(strategy, lamports);
+            return;
+        }
+    }
+    // a failed lookup lets the buy through, like an unfinished insider analysis
+    if limits.needs_holders() && ctx.executor.is_live() {
+        let stats = ctx
+            .holders
+            .refresh(client, &mint, &state.bonding_curve, &state.creator)
+            .await;
+        if let Some(stats) = stats {
+            let rejected = if limits.min_holders.is_some_and(|min| stats.fewer_than(min)) {
+                Some(format!(
+                    "{} holders (min {})",
+                    stats.holders,
+                    limits.min_holders.unwrap_or(0)
+                ))
+            } else if limits.max_top10_pct.is_some_and(|max| stats.top10_pct > max) {
+                Some(format!("top 10 hold {:.1}%", stats.top10_pct))
+            } else if limits.max_creator_pct.is_some_and(|max| stats.creator_pct > max) {
+                Some(format!("creator holds {:.1}%", stats.creator_pct))
+            } else {
+                None
+            };
+            if let Some(reason) = rejected {
+                println!(
+                    "{}:: {} {}, not buying",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    mint,
+                    reason
+                );
+                if ctx.positions.holders_of(&mint).is_empty() {
+                    ctx.holders.forget(&mint);
                 }
+                ctx.strategies.release(strategy, lamports);
+                return;
             }
         }
     }
-
-    // check and send,,,,,,,,
-    if user != Pubkey::default()
-        && mint != Pubkey::default()
-        && bc_pk != Pubkey::default()
-        && virtual_sol_reserves > 0
-        && virtual_token_reserves > 0
-    {
+    // the cluster is only known once the create slot's block is read
+    let insiders = match limits.max_insider_pct {
+        Some(_) => ctx.insiders.wait_for(&mint).await,
+        None => None,
+    };
+    if let (Some(max_pct), Some(report)) = (limits.max_insider_pct, &insiders) {
+        if report.supply_pct > max_pct {
+            println!(
+                "{}:: {} insiders hold {:.1}% of supply across {} wallets (max {}%), not buying",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                mint,
+                report.supply_pct,
+                report.cluster.len() + 1,
+                max_pct
+            );
+            ctx.strategies.release(strategy, lamports);
+            return;
+        }
+    }
+    // adding to a position stays in the wallet already holding it
+    let wallet_idx = match ctx.positions.get(strategy, &mint) {
+        Some(position) => position.wallet,
+        None => ctx.wallets.pick(strategy, &ctx.positions),
+    };
+    let wallet = ctx.wallets.get(wallet_idx);
+    let payer = &wallet.keypair;
+
+    // priced once, so the balance check covers exactly what gets sent
+    let prices_4_spam = ctx.fees.buy();
+
+    // make sure the wallet can pay for the worst case before committing
+    let requested = lamports;
+    let (lamports, balance_hold) = match &wallet.balance {
+        Some(balance) => {
+            let cost = balance.trade_cost(
+                (lamports as f64 * 1.03) as u64,
+                &ctx.unit_limit_ix,
+                &prices_4_spam,
+            );
+            match balance.fit_buy(lamports, &cost) {
+                Ok(fitted) => fitted,
+                Err(rejection) => {
+                    println!(
+                        "{}:: wallet {} can't afford buy of {}: {}",
+                        Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        wallet.pubkey,
+                        mint,
+                        rejection
+                    );
+                    ctx.strategies.release(strategy, requested);
+                    return;
+                }
+            }
+        }
+        None => (lamports, 0),
+    };
+    ctx.strategies.release(strategy, requested - lamports);
+    let release_balance = || {
+        if let Some(balance) = &wallet.balance {
+            balance.release(balance_hold);
+        }
+    };
+
+    if let Err(rejection) = ctx.risk.check_buy(
+        &ctx.positions,
+        strategy,
+        &mint,
+        &state.creator,
+        lamports,
+        ctx.executor.now_ms(),
+    ) {
         println!(
-            "{}:: user: {:?} \nmint: {:?}",
+            "{}:: risk rejected buy of {}: {}",
             Local::now().format("%Y-%m-%d %H:%M:%S"),
-            user,
-            mint
+            mint,
+            rejection
         );
-        // println!("-----------------");
-
-        let bc_pk_ata = Pubkey::find_program_address(
-            &[
-                &bc_pk.to_bytes(),
-                &TOKEN_PROGRAM_ID.to_bytes(),
-                &mint.to_bytes(),
-            ],
-            &ASSOCIATED_TOKEN_PROGRAM_ID,
-        )
-        .0;
+        ctx.strategies.release(strategy, lamports);
+        release_balance();
+        return;
+    }
 
-        // println!("BC ATA:{}", &bc_pk_ata);
+    let bc_pk = state.bonding_curve;
+    let bc_pk_ata = bonding_curve_ata(&bc_pk, &mint);
+
+    // price and tokens calcualtion
+    let investment_lamported = lamports as f64;
+    let adjusted_investment_for_fees = investment_lamported + (investment_lamported * 0.03);
+
+    let final_with_slippage_int = get_sol2tokens(
+        state.virtual_sol_reserves,
+        state.virtual_token_reserves,
+        investment_lamported,
+        ctx.slippage,
+    )
+    .await
+    .expect("Failed to get price, terminating program.");
+
+    println!("final_with_slippage_int: {}", final_with_slippage_int);
+
+    // --------------------------------
+    //create token ata.
+    let mint_ata = spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint);
+
+    let ix_ata: Instruction = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &mint,
+        &TOKEN_PROGRAM_ID,
+    );
+
+    // buy ix-----------
+    let buy_ix = get_buy_ix(
+        final_with_slippage_int as u64,
+        adjusted_investment_for_fees as u64,
+        mint,
+        bc_pk,
+        bc_pk_ata,
+        mint_ata,
+        payer.as_ref(),
+    )
+    .unwrap();
+
+    // tx info--------------------
+    let ixs: Vec<Instruction> = vec![ix_ata, buy_ix, ctx.unit_limit_ix.clone()];
+
+    // watch from the moment we commit, the creator may dump before our buy lands
+    rug_watch.watch(mint, state.creator);
+    if ctx.executor.is_live() {
+        let client = client.clone();
+        let rug_watch = rug_watch.clone();
+        let creator = state.creator;
+        let cluster = insiders.map(|report| report.cluster).unwrap_or_default();
+        tokio::spawn(async move {
+            // wallets in the insider cluster dump like the creator does
+            rug_watch.add_linked_wallets(&mint, cluster);
+            let limit = rug_watch.config().creator_scan_limit;
+            let funded = find_creator_funded_wallets(&client, &creator, limit).await;
+            rug_watch.add_linked_wallets(&mint, funded);
+        });
+    }
 
-        // price and tokens calcualtion
+    let nonce = ctx.nonces.as_ref().and_then(|nonces| nonces.lease(&wallet.pubkey));
+    let sent = ctx
+        .executor
+        .send_ladder(LadderRequest {
+            side: "buy",
+            mint: &mint,
+            token_amount: final_with_slippage_int as u64,
+            prices_4_spam: prices_4_spam.clone(),
+            client,
+            payer,
+            instructions_vec: &ixs,
+            lookup_table: ctx.lookup_table.as_ref(),
+            nonce: nonce.as_ref(),
+        })
+        .await;
+    if let (Some(nonces), Some(nonce)) = (&ctx.nonces, &nonce) {
+        nonces.release(nonce, sent.attempted);
+    }
+    let sent = sent.accepted;
+
+    if sent.is_none() {
+        release_balance();
+        if ctx.executor.is_live() {
+            ctx.fees.record_buy(LadderOutcome {
+                tier: None,
+                price: None,
+                slot_delay: None,
+            });
+        }
+        ctx.risk.on_buy_done(strategy, &mint);
+        ctx.strategies.release(strategy, lamports);
+        if ctx.positions.holders_of(&mint).is_empty() {
+            rug_watch.unwatch(&mint);
+            ctx.holders.forget(&mint);
+        }
+        return;
+    }
 
-        let final_with_slippage_int = get_sol2tokens(
-            virtual_sol_reserves,
-            virtual_token_reserves,
-            investment_lamported,
-            slippage,
-        )
-        .await
-        .expect("Failed to get price, terminating program.");
-
-        println!("final_with_slippage_int: {}", final_with_slippage_int);
-
-        // --------------------------------
-        //create token ata.
-        let mint_ata =
-            spl_associated_token_account::get_associated_token_address(&PAYER.pubkey(), &mint);
-
-        let ix_ata: Instruction =
-            spl_associated_token_account::instruction::create_associated_token_account(
-                &PAYER.pubkey(),
-                &PAYER.pubkey(),
-                &mint,
-                &TOKEN_PROGRAM_ID,
-            );
+    let now_ms = ctx.executor.now_ms();
+    ctx.positions.add(Position {
+        strategy,
+        wallet: wallet_idx,
+        mint,
+        bonding_curve: bc_pk,
+        creator: state.creator,
+        tokens: final_with_slippage_int as u64,
+        cost_lamports: lamports,
+        opened_ms: now_ms,
+    });
+    ctx.risk.on_buy_done(strategy, &mint);
+
+    // the position is open on the estimate so exits work while we wait for
+    // the confirmed amount; a buy that failed on chain is taken back
+    let estimated = final_with_slippage_int as u64;
+    let mut token_amount = estimated;
+    let mut landed_slot = ctx.slots.slot();
+    if let (true, Some((signature, tier))) = (ctx.executor.is_live(), sent) {
+        let delta = token_delta(client, &signature, &wallet.pubkey, &mint).await;
+        if let Some(delta) = delta {
+            landed_slot = delta.slot;
+        }
+        let landed = match delta {
+            Some(delta) => Some(delta.tokens),
+            None => ata_balance(client, &mint_ata).await,
+        };
+        // landed or known failed, the followed balance reflects the spend from here
+        release_balance();
+        if let Some(amount) = landed {
+            ctx.executor.record_outcome(&signature, amount > 0).await;
+        }
+        // the tuner needs the landing slot, which only the transaction has,
+        // and a create slot to measure it against
+        if let (Some(delta), true) = (delta, state.create_slot > 0) {
+            let price = prices_4_spam.get(tier).and_then(compute_unit_price);
+            let slot_delay = (delta.tokens > 0).then(|| delta.slot.saturating_sub(state.create_slot));
+            if let Some(delay) = slot_delay {
+                println!(
+                    "{}:: buy of {} landed in slot {} at tier {} ({:?} micro-lamports), {} slots after create in {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    mint,
+                    delta.slot,
+                    tier,
+                    price,
+                    delay,
+                    state.create_slot
+                );
+            }
+            ctx.fees.record_buy(LadderOutcome {
+                tier: Some(tier),
+                price,
+                slot_delay,
+            });
+        }
+        match landed {
+            Some(0) => {
+                println!(
+                    "{}:: buy of {} did not land: {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    mint,
+                    signature
+                );
+                ctx.positions.undo_add(strategy, &mint, estimated, lamports);
+                ctx.strategies.release(strategy, lamports);
+                if ctx.positions.holders_of(&mint).is_empty() {
+                    rug_watch.unwatch(&mint);
+                    ctx.holders.forget(&mint);
+                }
+                return;
+            }
+            Some(actual) => {
+                ctx.positions.adjust_tokens(strategy, &mint, estimated, actual);
+                token_amount = actual;
+            }
+            None => {}
+        }
+    }
 
-        // buy ix-----------
-        let buy_ix = get_buy_ix(
-            final_with_slippage_int as u64,
-            adjusted_investment_for_fees as u64,
-            mint,
-            bc_pk,
-            bc_pk_ata,
-            mint_ata,
-            PAYER.as_ref(),
-        )
-        .unwrap();
+    let fill = Fill {
+        mint,
+        side: Side::Buy,
+        token_amount,
+        lamports,
+        time_ms: now_ms,
+        slot: landed_slot,
+    };
+    ctx.bus.publish(Event::Fill { strategy, fill });
+}
 
-        // tx info--------------------
-        let ixs: Vec<Instruction> = vec![ix_ata, buy_ix, unit_limit_ix.clone()];
-
-        spammer(prices_4_spam.clone(), &client, &PAYER, &m_pk, &ixs).await;
-
-        // // incase you wanted to exit on specific profits......  not fully implemented
-        // let mut account_token_balance = 0;
-        // let start_checking_balance = Instant::now();
-        // loop {
-        //     match client
-        //         .get_token_account_balance_with_commitment(&mint_ata, CommitmentConfig::processed())
-        //         .await
-        //     {
-        //         Ok(account_balance) => {
-        //             let amount: u64 = account_balance.value.amount.parse::<u64>().unwrap_or(0);
-        //             account_token_balance = amount;
-        //             if amount > 0 {
-        //                 println!(
-        //                     "{}::Balance found: {:?}",
-        //                     Local::now().format("%Y-%m-%d %H:%M:%S"),
-        //                     &amount
-        //                 );
-
-        //                 let duration = start_checking_balance.elapsed(); //current time
-        //                 println!("Time Consumed to amount shown in account: {:?}", duration); //print it
-        //                 break;
-        //             }
-        //         }
-        //         Err(e) => {
-        //             tokio::time::sleep(Duration::from_millis(100)).await;
-        //             // println!("Failed to fetch balance: {:?}", e);
-        //             // if start_checking_balance.elapsed() > Duration::from_secs(20) {
-        //             //     println!("Balance not found");
-        //             //     break;
-        //             // }
-        //         }
-        //     }
-        // }
-
-        //----------------------------------------------------------------
-        //----------------------------------------------------------------
-        println!("Going to sleep"); //----------------------------------------------------------------
-        tokio::time::sleep(Duration::from_secs(10)).await;
-        //----------------------------------------------------------------
-        //----------------------------------------------------------------
-        //----------------------------------------------------------------
-
-        let sell_ix = create_sell_ix(
-            final_with_slippage_int as u64,
-            0 as u64,
-            mint,
-            bc_pk,
-            bc_pk_ata,
-            mint_ata,
-            PAYER.as_ref(),
-        )
-        .unwrap();
+/// Sells `pct` of a strategy's position. `emergency` switches to the rug fee ladder.
+pub async fn execute_sell(ctx: &Arc<TradeContext>, strategy: usize, mint: Pubkey, pct: f64, emergency: bool) {
+    let position = match ctx.positions.get(strategy, &mint) {
+        Some(position) => position,
+        None => return,
+    };
+    let wallet = ctx.wallets.get(position.wallet);
+    let payer = &wallet.keypair;
+    let mint_ata = spl_associated_token_account::get_associated_token_address(&payer.pubkey(), &mint);
+
+    // a full exit from a token account no other strategy shares sells
+    // whatever the account really holds and closes it for the rent
+    let closes = pct >= 1.0 && ctx.positions.holders_in_wallet(&mint, position.wallet).len() == 1;
+    let tokens = if closes {
+        let held = if ctx.executor.is_live() {
+            ata_balance(&ctx.client, &mint_ata).await
+        } else {
+            None
+        };
+        match held {
+            Some(0) => {
+                println!(
+                    "{}:: {} holds no {}, dropping the position",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    wallet.pubkey,
+                    mint
+                );
+                let cost = ctx.positions.reduce(strategy, &mint, position.tokens);
+                ctx.strategies.release(strategy, cost);
+                if ctx.positions.holders_of(&mint).is_empty() {
+                    ctx.rug_watch.unwatch(&mint);
+                    ctx.holders.forget(&mint);
+                }
+                return;
+            }
+            Some(held) => held,
+            None => position.tokens,
+        }
+    } else if pct >= 1.0 {
+        position.tokens
+    } else {
+        (position.tokens as f64 * pct) as u64
+    };
+    if tokens == 0 {
+        return;
+    }
 
+    let bc_pk = position.bonding_curve;
+    let bc_pk_ata = bonding_curve_ata(&bc_pk, &mint);
+
+    let sell_ix = create_sell_ix(
+        tokens,
+        0,
+        mint,
+        bc_pk,
+        bc_pk_ata,
+        mint_ata,
+        payer.as_ref(),
+    )
+    .unwrap();
+
+    let mut ixs_sell: Vec<Instruction> = vec![sell_ix];
+    if closes {
         let close_acc_ix = close_account(
             &TOKEN_PROGRAM_ID,
             &mint_ata,
-            &PAYER.pubkey(),
-            &PAYER.pubkey(),
-            &[&PAYER.pubkey()],
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &[&payer.pubkey()],
         )
         .unwrap();
+        ixs_sell.push(close_acc_ix);
+    }
+    ixs_sell.push(ctx.unit_limit_ix.clone());
 
-        let ixs_sell: Vec<Instruction> = vec![sell_ix, unit_limit_ix.clone()];
-
-        //          let recent_blockhash1 = client.get_latest_blockhash_with_commitment(CommitmentConfig::processed()).await.unwrap(); //get blockhash
-        //        let tx = Transaction::new_signed_with_payer(&ixs_sell,Some(&PAYER.pubkey()), &[&PAYER], recent_blockhash1.0);
-        //            let sig = client.send_transaction(&tx).await.unwrap();
-        //   println!("sig: {}",&sig.to_string());
+    //          let recent_blockhash1 = client.get_latest_blockhash_with_commitment(CommitmentConfig::processed()).await.unwrap(); //get blockhash
+    //        let tx = Transaction::new_signed_with_payer(&ixs_sell,Some(&PAYER.pubkey()), &[&PAYER], recent_blockhash1.0);
+    //            let sig = client.send_transaction(&tx).await.unwrap();
+    //   println!("sig: {}",&sig.to_string());
 
+    let sell_ladder = if emergency {
+        println!("going to spam emergency sell");
+        ctx.fees.rug()
+    } else {
         println!("going to spam sell");
-        spammer(prices_4_spam.to_vec(), &client, &PAYER, &m_pk, &ixs_sell).await;
+        ctx.fees.sell()
+    };
+    let nonce = ctx.nonces.as_ref().and_then(|nonces| nonces.lease(&wallet.pubkey));
+    let sent = ctx
+        .executor
+        .send_ladder(LadderRequest {
+            side: "sell",
+            mint: &mint,
+            token_amount: tokens,
+            prices_4_spam: sell_ladder,
+            client: &ctx.client,
+            payer,
+            instructions_vec: &ixs_sell,
+            lookup_table: ctx.lookup_table.as_ref(),
+            nonce: nonce.as_ref(),
+        })
+        .await;
+    if let (Some(nonces), Some(nonce)) = (&ctx.nonces, &nonce) {
+        nonces.release(nonce, sent.attempted);
+    }
 
-        println!("{}::DOne", Local::now().format("%Y-%m-%d %H:%M:%S"));
-        println!("------------------------------------------------------------------");
+    let signature = match sent.accepted {
+        Some((signature, _)) => signature,
+        None => {
+            // the strategy has to know, or it never tries to exit again
+            ctx.bus.publish(Event::SellFailed { strategy, mint });
+            return;
+        }
+    };
+
+    // only what the chain confirms leaves the position; replay has no chain
+    let sold = if ctx.executor.is_live() {
+        let sold = match token_delta(&ctx.client, &signature, &wallet.pubkey, &mint).await {
+            Some(delta) => Some(delta.sold),
+            // a closing sell that landed took the account with it
+            None if closes => match ata_balance(&ctx.client, &mint_ata).await {
+                Some(left) => Some(tokens.saturating_sub(left)),
+                None if ata_closed(&ctx.client, &mint_ata).await => Some(tokens),
+                None => None,
+            },
+            None => None,
+        };
+        if let Some(sold) = sold {
+            ctx.executor.record_outcome(&signature, sold > 0).await;
+        }
+        match sold {
+            Some(0) | None => {
+                println!(
+                    "{}:: sell of {} did not land or could not be confirmed, keeping the position: {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    mint,
+                    signature
+                );
+                ctx.bus.publish(Event::SellFailed { strategy, mint });
+                return;
+            }
+            Some(sold) => sold.min(tokens),
+        }
+    } else {
+        tokens
+    };
+
+    // proceeds are estimated from the last curve state we saw
+    let proceeds = ctx
+        .positions
+        .mint_state(&mint)
+        .map(|state| curve_sell_sol(state.virtual_sol_reserves, state.virtual_token_reserves, sold))
+        .unwrap_or(0);
+
+    // the account is closed, nothing of the position is left
+    let reduced = if closes && sold == tokens { sold.max(position.tokens) } else { sold };
+    let cost = ctx.positions.reduce(strategy, &mint, reduced);
+    ctx.strategies.release(strategy, cost);
+    ctx.risk.on_sell(cost, proceeds);
+    if ctx.positions.holders_of(&mint).is_empty() {
+        ctx.rug_watch.unwatch(&mint);
+        ctx.holders.forget(&mint);
     }
+
+    println!("{}::DOne", Local::now().format("%Y-%m-%d %H:%M:%S"));
+    println!("------------------------------------------------------------------");
+
+    let fill = Fill {
+        mint,
+        side: Side::Sell,
+        token_amount: sold,
+        lamports: proceeds,
+        time_ms: ctx.executor.now_ms(),
+        slot: ctx.slots.slot(),
+    };
+    ctx.bus.publish(Event::Fill { strategy, fill });
 }
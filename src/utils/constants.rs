@@ -22,6 +22,16 @@ pub const PUMPFUN_EVENT_AUTHORITY: Pubkey = pubkey!("Ce6TQqeHC9p8KetsN6JsjHK7UTZ
 pub const DEFAULT_BUY: [u8; 8] = [0x66, 0x06, 0x3d, 0x12, 0x01, 0xda, 0xeb, 0xea];
 pub const DEFAULT_SELL: [u8; 8] = [0x33, 0xe6, 0x85, 0xa4, 0x01, 0x7f, 0x83, 0xad];
 
+// anchor event discriminators (sha256("event:<Name>")[..8])
+pub const CREATE_EVENT_DISCRIMINATOR: [u8; 8] = [0x1b, 0x72, 0xa9, 0x4d, 0xde, 0xeb, 0x63, 0x76];
+pub const TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [0xbd, 0xdb, 0x7f, 0xd3, 0x4e, 0xe6, 0x61, 0xee];
+
+// bonding curve starting state
+pub const PUMPFUN_INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+pub const PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
+pub const PUMPFUN_FEE_BASIS_POINTS: u64 = 100;
+pub const PUMPFUN_TOTAL_SUPPLY: u64 = 1_000_000_000_000_000;
+
 pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 pub const LIQUIDITY_FEES_NUMERATOR: u32 = 25;
 pub const LIQUIDITY_FEES_DENOMINATOR: u32 = 10_000;
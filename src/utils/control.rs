@@ -0,0 +1,430 @@
+use chrono::Local;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::env;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use hyper::body::HttpBody;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use solana_sdk::pubkey::Pubkey;
+
+use super::bus::Event;
+use super::check_logs_buy_sell::TradeContext;
+use super::constants::LAMPORTS_PER_SOL;
+use super::pf_price::curve_sell_sol;
+use super::pipeline::PipelineMetrics;
+use crate::strategy::runner::EntryLimits;
+use crate::strategy::Intent;
+use crate::wallet::reclaim::reclaim_wallet;
+
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Loaded from env, see README. The API only runs when a token is set.
+#[derive(Clone, Debug)]
+pub struct ControlConfig {
+    pub addr: SocketAddr,
+    pub token: String,
+}
+
+impl ControlConfig {
+    pub fn from_env() -> Option<Self> {
+        let token = env::var("control_token").ok()?;
+        assert!(token.len() >= 16, "control_token must be at least 16 characters");
+
+        let addr = env::var("control_addr")
+            .map(|v| v.parse::<SocketAddr>().expect("control_addr must be a valid socket address"))
+            .unwrap_or_else(|_| SocketAddr::from(([127, 0, 0, 1], 8787)));
+        // the token travels in clear text, keep it off the network
+        assert!(addr.ip().is_loopback(), "control_addr must be a loopback address");
+
+        Some(ControlConfig { addr, token })
+    }
+}
+
+type ApiResult = Result<Value, (StatusCode, String)>;
+
+fn bad_request(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, msg.into())
+}
+
+fn not_found(msg: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, msg.into())
+}
+
+fn log(msg: String) {
+    println!("{}:: control: {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg);
+}
+
+/// Serves the control API on `config.addr` until the process exits.
+pub fn spawn(config: ControlConfig, ctx: Arc<TradeContext>, metrics: Arc<PipelineMetrics>) {
+    let token = Arc::new(config.token);
+    let make_service = make_service_fn(move |_| {
+        let (ctx, metrics, token) = (ctx.clone(), metrics.clone(), token.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let (ctx, metrics, token) = (ctx.clone(), metrics.clone(), token.clone());
+                async move { Ok::<_, Infallible>(serve(&ctx, &metrics, &token, req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&config.addr)
+        .unwrap_or_else(|e| panic!("Failed to bind control API on {}: {}", config.addr, e))
+        .serve(make_service);
+    println!("Control API listening on {}", config.addr);
+
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            eprintln!("Control API stopped: {:?}", e);
+        }
+    });
+}
+
+async fn serve(ctx: &Arc<TradeContext>, metrics: &PipelineMetrics, token: &str, req: Request<Body>) -> Response<Body> {
+    let authorised = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| same_token(given, token));
+
+    let result = if !authorised {
+        Err((StatusCode::UNAUTHORIZED, "missing or wrong bearer token".to_string()))
+    } else {
+        route(ctx, metrics, req).await
+    };
+
+    let (status, body) = match result {
+        Ok(body) => (StatusCode::OK, body),
+        Err((status, error)) => (status, json!({ "error": error })),
+    };
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+// compares every byte so the time taken does not reveal the matching prefix
+fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn route(ctx: &Arc<TradeContext>, metrics: &PipelineMetrics, req: Request<Body>) -> ApiResult {
+    let method = req.method().clone();
+    let path: Vec<String> = req
+        .uri()
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(str::to_string)
+        .collect();
+    let body = read_body(req).await?;
+    let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+
+    match (&method, segments.as_slice()) {
+        (&Method::GET, ["status"]) => Ok(status(ctx, metrics)),
+        (&Method::POST, ["entries", "pause"]) => {
+            ctx.risk.set_paused(true);
+            log("entries paused".to_string());
+            Ok(json!({ "paused": true }))
+        }
+        (&Method::POST, ["entries", "resume"]) => {
+            ctx.risk.set_paused(false);
+            log("entries resumed".to_string());
+            Ok(json!({ "paused": false }))
+        }
+        (&Method::POST, ["risk", "reset"]) => {
+            ctx.risk.reset();
+            log("daily loss and halt reset".to_string());
+            Ok(json!({ "halted": false }))
+        }
+        (&Method::GET, ["positions"]) => Ok(positions(ctx)),
+        (&Method::POST, ["positions", mint, "sell"]) => force_sell(ctx, mint, &body),
+        (&Method::GET, ["strategies"]) => Ok(strategies(ctx)),
+        (&Method::PATCH, ["strategies", idx]) => update_strategy(ctx, idx, &body),
+        (&Method::GET, ["strategies", idx, "copy"]) => copied(ctx, strategy_index(ctx, idx)?),
+        (&Method::POST, ["strategies", idx, "copy"]) => {
+            let idx = strategy_index(ctx, idx)?;
+            let wallet = parse_pubkey(body.get("wallet").and_then(Value::as_str).unwrap_or(""))?;
+            if copy_list(ctx, idx, |wallets| wallets.insert(wallet))? {
+                log(format!("{} now copies {}", ctx.strategies.name(idx), wallet));
+            }
+            copied(ctx, idx)
+        }
+        (&Method::DELETE, ["strategies", idx, "copy", wallet]) => {
+            let idx = strategy_index(ctx, idx)?;
+            let wallet = parse_pubkey(wallet)?;
+            if !copy_list(ctx, idx, |wallets| wallets.remove(&wallet))? {
+                return Err(not_found(format!("{} is not copied", wallet)));
+            }
+            log(format!("{} no longer copies {}", ctx.strategies.name(idx), wallet));
+            copied(ctx, idx)
+        }
+        (&Method::GET, ["deny"]) => Ok(denied(ctx)),
+        (&Method::POST, ["deny"]) => {
+            let creator = parse_pubkey(body.get("creator").and_then(Value::as_str).unwrap_or(""))?;
+            if ctx.risk.deny_creator(creator) {
+                log(format!("denied creator {}", creator));
+            }
+            Ok(denied(ctx))
+        }
+        (&Method::DELETE, ["deny", creator]) => {
+            let creator = parse_pubkey(creator)?;
+            if !ctx.risk.allow_creator(&creator) {
+                return Err(not_found(format!("{} is not denied", creator)));
+            }
+            log(format!("allowed creator {}", creator));
+            Ok(denied(ctx))
+        }
+        (&Method::POST, ["reclaim"]) => reclaim(ctx, &body).await,
+        _ => Err(not_found(format!("no route for {} {}", method, path.join("/")))),
+    }
+}
+
+// an empty body reads as an empty object
+async fn read_body(req: Request<Body>) -> Result<Value, (StatusCode, String)> {
+    let mut body = req.into_body();
+    // chunked bodies declare no length, so the bytes are counted as they come
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| bad_request(format!("failed to read body: {}", e)))?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, "body too large".to_string()));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    if bytes.is_empty() {
+        return Ok(json!({}));
+    }
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) if value.is_object() => Ok(value),
+        Ok(_) => Err(bad_request("body must be a JSON object")),
+        Err(e) => Err(bad_request(format!("invalid JSON: {}", e))),
+    }
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, (StatusCode, String)> {
+    Pubkey::from_str(value).map_err(|_| bad_request(format!("{:?} is not a valid pubkey", value)))
+}
+
+fn status(ctx: &Arc<TradeContext>, metrics: &PipelineMetrics) -> Value {
+    json!({
+        "paused": ctx.risk.is_paused(),
+        "halted": ctx.risk.is_halted(),
+        "realised_pnl_lamports": ctx.risk.realised_pnl_lamports(),
+        "open_positions": ctx.positions.all().len(),
+        "wallets": ctx.wallets.len(),
+        "events": metrics.snapshot(),
+    })
+}
+
+// marked to the last seen curve, net of the pump.fun fee
+fn positions(ctx: &Arc<TradeContext>) -> Value {
+    let mut unrealised = 0i64;
+    let positions: Vec<Value> = ctx
+        .positions
+        .all()
+        .into_iter()
+        .map(|p| {
+            let value = ctx
+                .positions
+                .mint_state(&p.mint)
+                .map(|s| curve_sell_sol(s.virtual_sol_reserves, s.virtual_token_reserves, p.tokens));
+            let pnl = value.map(|v| v as i64 - p.cost_lamports as i64);
+            unrealised += pnl.unwrap_or(0);
+            json!({
+                "strategy": p.strategy,
+                "strategy_name": ctx.strategies.name(p.strategy),
+                "wallet": ctx.wallets.get(p.wallet).pubkey.to_string(),
+                "mint": p.mint.to_string(),
+                "tokens": p.tokens,
+                "cost_lamports": p.cost_lamports,
+                "value_lamports": value,
+                "pnl_lamports": pnl,
+                "pnl_pct": pnl.filter(|_| p.cost_lamports > 0).map(|pnl| pnl as f64 / p.cost_lamports as f64 * 100.0),
+                "opened_ms": p.opened_ms,
+            })
+        })
+        .collect();
+
+    json!({
+        "positions": positions,
+        "unrealised_pnl_lamports": unrealised,
+        "realised_pnl_lamports": ctx.risk.realised_pnl_lamports(),
+    })
+}
+
+// the same sell the strategies would publish, or the rug exit with `emergency`
+fn force_sell(ctx: &Arc<TradeContext>, mint: &str, body: &Value) -> ApiResult {
+    let mint = parse_pubkey(mint)?;
+    let strategy = match body.get("strategy") {
+        None | Some(Value::Null) => None,
+        Some(v) => Some(v.as_u64().ok_or_else(|| bad_request("strategy must be an index"))? as usize),
+    };
+    let pct = match body.get("pct") {
+        None | Some(Value::Null) => 1.0,
+        Some(v) => v
+            .as_f64()
+            .filter(|pct| *pct > 0.0 && *pct <= 1.0)
+            .ok_or_else(|| bad_request("pct must be in (0, 1]"))?,
+    };
+    let emergency = body.get("emergency").and_then(Value::as_bool).unwrap_or(false);
+    if emergency && pct < 1.0 {
+        return Err(bad_request("an emergency sell always sells everything"));
+    }
+
+    let holders: Vec<usize> = ctx
+        .positions
+        .holders_of(&mint)
+        .into_iter()
+        .filter(|idx| strategy.is_none_or(|s| s == *idx))
+        .collect();
+    if holders.is_empty() {
+        return Err(not_found(format!("no open position in {}", mint)));
+    }
+
+    for strategy in &holders {
+        let event = match emergency {
+            true => Event::EmergencySell {
+                strategy: *strategy,
+                mint,
+            },
+            false => Event::Intent {
+                strategy: *strategy,
+                intent: Intent::Sell { mint, pct },
+            },
+        };
+        ctx.bus.publish(event);
+    }
+    log(format!("selling {:.0}% of {} for strategies {:?}", pct * 100.0, mint, holders));
+    Ok(json!({ "mint": mint.to_string(), "strategies": holders, "pct": pct }))
+}
+
+fn strategy_json(ctx: &Arc<TradeContext>, idx: usize) -> Value {
+    let budget = ctx.strategies.budget_lamports(idx);
+    json!({
+        "index": idx,
+        "name": ctx.strategies.name(idx),
+        "investment_lamports": ctx.strategies.investment(idx),
+        "budget_lamports": if budget == u64::MAX { None } else { Some(budget) },
+        "committed_lamports": ctx.strategies.committed_lamports(idx),
+        "limits": ctx.strategies.limits(idx),
+    })
+}
+
+fn strategies(ctx: &Arc<TradeContext>) -> Value {
+    Value::Array((0..ctx.strategies.len()).map(|idx| strategy_json(ctx, idx)).collect())
+}
+
+fn strategy_index(ctx: &Arc<TradeContext>, idx: &str) -> Result<usize, (StatusCode, String)> {
+    idx.parse::<usize>()
+        .ok()
+        .filter(|idx| *idx < ctx.strategies.len())
+        .ok_or_else(|| not_found(format!("no strategy {}", idx)))
+}
+
+// `limits` is merged into the current limits, a null clears one
+fn update_strategy(ctx: &Arc<TradeContext>, idx: &str, body: &Value) -> ApiResult {
+    let idx = strategy_index(ctx, idx)?;
+
+    if let Some(key) = body
+        .as_object()
+        .and_then(|o| o.keys().find(|k| *k != "investment_sol" && *k != "limits"))
+    {
+        return Err(bad_request(format!("unknown field {}", key)));
+    }
+
+    let limits = match body.get("limits") {
+        Some(patch) => Some(merge_limits(ctx.strategies.limits(idx), patch)?),
+        None => None,
+    };
+    let investment = match body.get("investment_sol") {
+        Some(v) => Some(
+            v.as_f64()
+                .filter(|sol| *sol > 0.0)
+                .map(|sol| (sol * LAMPORTS_PER_SOL as f64) as u64)
+                .ok_or_else(|| bad_request("investment_sol must be a positive number"))?,
+        ),
+        None => None,
+    };
+
+    // checked before the limits are touched so a rejected request changes nothing
+    if let Some(lamports) = investment {
+        if !ctx.strategies.set_investment(idx, lamports) {
+            return Err(bad_request(format!("{} has no fixed investment", ctx.strategies.name(idx))));
+        }
+        log(format!("{} now invests {} lamports", ctx.strategies.name(idx), lamports));
+    }
+    if let Some(limits) = limits {
+        log(format!("{} entry limits now {:?}", ctx.strategies.name(idx), limits));
+        ctx.strategies.set_limits(idx, limits);
+    }
+    Ok(strategy_json(ctx, idx))
+}
+
+fn merge_limits(current: EntryLimits, patch: &Value) -> Result<EntryLimits, (StatusCode, String)> {
+    let patch = patch.as_object().ok_or_else(|| bad_request("limits must be an object"))?;
+    let mut merged = serde_json::to_value(current).unwrap();
+    for (key, value) in patch {
+        match merged.get_mut(key) {
+            Some(slot) => *slot = value.clone(),
+            None => return Err(bad_request(format!("unknown limit {}", key))),
+        }
+    }
+    serde_json::from_value(merged).map_err(|e| bad_request(format!("invalid limits: {}", e)))
+}
+
+fn copy_list<R>(
+    ctx: &Arc<TradeContext>,
+    idx: usize,
+    edit: impl FnOnce(&mut HashSet<Pubkey>) -> R,
+) -> Result<R, (StatusCode, String)> {
+    ctx.strategies
+        .with_copy_list(idx, edit)
+        .ok_or_else(|| bad_request(format!("{} copies no wallets", ctx.strategies.name(idx))))
+}
+
+fn copied(ctx: &Arc<TradeContext>, idx: usize) -> ApiResult {
+    let mut wallets: Vec<String> = copy_list(ctx, idx, |wallets| wallets.iter().map(Pubkey::to_string).collect())?;
+    wallets.sort();
+    Ok(json!({ "strategy": idx, "wallets": wallets }))
+}
+
+fn denied(ctx: &Arc<TradeContext>) -> Value {
+    let creators: Vec<String> = ctx.risk.denied_creators().iter().map(Pubkey::to_string).collect();
+    json!({ "creators": creators })
+}
+
+// mints still held are never closed or sold as dust
+async fn reclaim(ctx: &Arc<TradeContext>, body: &Value) -> ApiResult {
+    let dust_lamports = match body.get("dust_sol") {
+        None | Some(Value::Null) => 0,
+        Some(v) => v
+            .as_f64()
+            .filter(|sol| *sol >= 0.0)
+            .map(|sol| (sol * LAMPORTS_PER_SOL as f64) as u64)
+            .ok_or_else(|| bad_request("dust_sol must be a non-negative number"))?,
+    };
+    let dry_run = body.get("dry_run").and_then(Value::as_bool).unwrap_or(false);
+    let keep: HashSet<Pubkey> = ctx.positions.all().iter().map(|p| p.mint).collect();
+
+    log(format!("reclaiming rent (dust {} lamports, dry run {})", dust_lamports, dry_run));
+    let mut reports = Vec::new();
+    for wallet in ctx.wallets.wallets() {
+        let report = reclaim_wallet(&ctx.client, &wallet.keypair, dust_lamports, &keep, dry_run).await;
+        reports.push(json!({
+            "wallet": wallet.pubkey.to_string(),
+            "closed": report.closed,
+            "dust_sold": report.dust_sold,
+            "skipped": report.skipped,
+            "transactions": report.transactions,
+            "reclaimed_lamports": report.reclaimed_lamports,
+        }));
+    }
+    Ok(json!({ "dry_run": dry_run, "wallets": reports }))
+}
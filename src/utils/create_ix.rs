@@ -22,6 +22,22 @@ pub struct SellLayoutpf {
 
 // ix----------------------------------
 
+pub fn bonding_curve_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", &mint.to_bytes()], &PUMPFUN_PROGRAM).0
+}
+
+pub fn bonding_curve_ata(bc_pk: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &bc_pk.to_bytes(),
+            &TOKEN_PROGRAM_ID.to_bytes(),
+            &mint.to_bytes(),
+        ],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
 pub fn get_buy_ix(
     final_with_slippage_int: u64,
     adjusted_investment_for_fees: u64,
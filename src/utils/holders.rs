@@ -0,0 +1,250 @@
+use chrono::Local;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::Account as TokenAccount;
+
+use super::constants::{ACCOUNT_LEN, PUMPFUN_TOTAL_SUPPLY, TOKEN_PROGRAM_ID};
+use super::create_ix::bonding_curve_ata;
+use super::token_balance::ata_balance;
+
+// getTokenLargestAccounts returns at most this many, the bonding curve's among them
+const LARGEST_ACCOUNTS: usize = 20;
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct HolderConfig {
+    /// Refresh held mints as they trade.
+    pub enabled: bool,
+    /// Scan every token account of the mint rather than the 20 largest.
+    pub full_scan: bool,
+    /// Trades on a mint between two refreshes.
+    pub refresh_trades: usize,
+    /// Least time between two refreshes of a mint.
+    pub refresh_ms: i64,
+}
+
+impl HolderConfig {
+    pub fn from_env() -> Self {
+        let enabled = env::var("holder_analysis")
+            .map(|v| v.parse::<bool>().expect("holder_analysis must be true or false"))
+            .unwrap_or(false);
+
+        let full_scan = env::var("holder_full_scan")
+            .map(|v| v.parse::<bool>().expect("holder_full_scan must be true or false"))
+            .unwrap_or(false);
+
+        let refresh_trades = env::var("holder_refresh_trades")
+            .map(|v| v.parse::<usize>().expect("holder_refresh_trades must be a valid usize"))
+            .unwrap_or(10);
+
+        let refresh_ms = env::var("holder_refresh_ms")
+            .map(|v| v.parse::<i64>().expect("holder_refresh_ms must be a valid i64"))
+            .unwrap_or(5_000);
+
+        HolderConfig {
+            enabled,
+            full_scan,
+            refresh_trades: refresh_trades.max(1),
+            refresh_ms,
+        }
+    }
+}
+
+/// How a mint's supply is spread, the bonding curve's own account aside.
+#[derive(Clone, Debug)]
+pub struct HolderStats {
+    /// Accounts holding a non-zero balance. Without a full scan only the
+    /// 20 largest are seen, so this is a lower bound.
+    pub holders: usize,
+    pub exact: bool,
+    /// Share of the total supply, in percent, held by the 10 largest holders.
+    pub top10_pct: f64,
+    pub creator_pct: f64,
+    pub fetched_ms: i64,
+}
+
+impl HolderStats {
+    /// Whether there are surely fewer than `min` holders. A count that
+    /// filled the largest accounts list could be short of the real one.
+    pub fn fewer_than(&self, min: usize) -> bool {
+        self.holders < min && (self.exact || self.holders < LARGEST_ACCOUNTS - 1)
+    }
+}
+
+#[derive(Default)]
+struct MintHolders {
+    stats: Option<HolderStats>,
+    trades_since: usize,
+    refreshing: bool,
+}
+
+/// Holder distribution per mint, refreshed on demand and as the mint trades.
+pub struct HolderAnalyser {
+    config: HolderConfig,
+    mints: Mutex<HashMap<Pubkey, MintHolders>>,
+}
+
+impl HolderAnalyser {
+    pub fn new(config: HolderConfig) -> Self {
+        HolderAnalyser {
+            config,
+            mints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Counts a trade on `mint` and claims a refresh when one is due. The
+    /// caller must follow up with `refresh`.
+    pub fn on_trade(&self, mint: &Pubkey) -> bool {
+        let mut mints = self.mints.lock().unwrap();
+        let holders = mints.entry(*mint).or_default();
+        holders.trades_since += 1;
+
+        let now_ms = Local::now().timestamp_millis();
+        let stale = holders
+            .stats
+            .as_ref()
+            .is_none_or(|s| now_ms - s.fetched_ms >= self.config.refresh_ms);
+        if holders.refreshing || holders.trades_since < self.config.refresh_trades || !stale {
+            return false;
+        }
+        holders.refreshing = true;
+        true
+    }
+
+    pub fn forget(&self, mint: &Pubkey) {
+        self.mints.lock().unwrap().remove(mint);
+    }
+
+    /// Fetches the distribution of `mint` and caches it. `None` if the RPC
+    /// could not be read.
+    pub async fn refresh(
+        &self,
+        client: &RpcClient,
+        mint: &Pubkey,
+        bonding_curve: &Pubkey,
+        creator: &Pubkey,
+    ) -> Option<HolderStats> {
+        let stats = match self.config.full_scan {
+            true => full_scan(client, mint, bonding_curve, creator).await,
+            false => largest_accounts(client, mint, bonding_curve, creator).await,
+        };
+        if let Err(e) = &stats {
+            eprintln!("Failed to fetch holders of {}: {}", mint, e);
+        }
+
+        let mut mints = self.mints.lock().unwrap();
+        let holders = mints.entry(*mint).or_default();
+        holders.refreshing = false;
+        if let Ok(stats) = &stats {
+            holders.stats = Some(stats.clone());
+            holders.trades_since = 0;
+        }
+        stats.ok()
+    }
+}
+
+fn pct(amount: u64) -> f64 {
+    amount as f64 / PUMPFUN_TOTAL_SUPPLY as f64 * 100.0
+}
+
+fn summarise(mut balances: Vec<u64>, creator_tokens: u64, exact: bool) -> HolderStats {
+    balances.retain(|amount| *amount > 0);
+    balances.sort_unstable_by(|a, b| b.cmp(a));
+    HolderStats {
+        holders: balances.len(),
+        exact,
+        top10_pct: pct(balances.iter().take(10).sum()),
+        creator_pct: pct(creator_tokens),
+        fetched_ms: Local::now().timestamp_millis(),
+    }
+}
+
+// one getTokenLargestAccounts call, plus the creator's ATA when it is not among them
+async fn largest_accounts(
+    client: &RpcClient,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    creator: &Pubkey,
+) -> Result<HolderStats, String> {
+    let curve_ata = bonding_curve_ata(bonding_curve, mint);
+    let creator_ata = spl_associated_token_account::get_associated_token_address(creator, mint);
+
+    let accounts = client
+        .get_token_largest_accounts_with_commitment(mint, CommitmentConfig::confirmed())
+        .await
+        .map_err(|e| e.to_string())?
+        .value;
+
+    let mut balances = Vec::new();
+    let mut creator_tokens = None;
+    for account in accounts {
+        let address = Pubkey::from_str(&account.address).map_err(|e| e.to_string())?;
+        let amount = account.amount.amount.parse::<u64>().unwrap_or(0);
+        if address == curve_ata {
+            continue;
+        }
+        if address == creator_ata {
+            creator_tokens = Some(amount);
+        }
+        balances.push(amount);
+    }
+    let creator_tokens = match creator_tokens {
+        Some(tokens) => tokens,
+        None => ata_balance(client, &creator_ata).await.unwrap_or(0),
+    };
+
+    Ok(summarise(balances, creator_tokens, false))
+}
+
+// every token account of the mint, summed per owner
+async fn full_scan(
+    client: &RpcClient,
+    mint: &Pubkey,
+    bonding_curve: &Pubkey,
+    creator: &Pubkey,
+) -> Result<HolderStats, String> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(ACCOUNT_LEN as u64),
+            // the mint is the first field of the token account layout
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &mint.to_bytes())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = client
+        .get_program_accounts_with_config(&TOKEN_PROGRAM_ID, config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut owners: HashMap<Pubkey, u64> = HashMap::new();
+    for (_, account) in accounts {
+        if let Ok(state) = TokenAccount::unpack(&account.data) {
+            if state.owner != *bonding_curve {
+                *owners.entry(state.owner).or_default() += state.amount;
+            }
+        }
+    }
+    let creator_tokens = owners.get(creator).copied().unwrap_or(0);
+
+    Ok(summarise(owners.into_values().collect(), creator_tokens, true))
+}
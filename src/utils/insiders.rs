@@ -0,0 +1,264 @@
+use chrono::Local;
+use futures::future::join_all;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::sync::Mutex;
+
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcBlockConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use solana_transaction_status::{TransactionDetails, UiTransactionEncoding};
+use tokio::time::{sleep, Duration, Instant};
+
+use super::constants::{PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES, PUMPFUN_TOTAL_SUPPLY};
+use super::pf_events::decode_trade_events;
+use super::rug_watch::{find_creator_funded_wallets, find_funders};
+use super::snipers::SniperBoard;
+
+// launches whose reports we keep, oldest are forgotten first
+const MAX_LAUNCHES: usize = 2_000;
+// the create slot is usually confirmed within a few hundred ms
+const BLOCK_ATTEMPTS: u32 = 10;
+const BLOCK_DELAY: Duration = Duration::from_millis(400);
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct InsiderConfig {
+    pub enabled: bool,
+    /// Transactions scanned per wallet when looking for who funded it.
+    pub funding_scan_limit: usize,
+    /// Funders of more than this many buyers, likely exchange hot wallets, link nobody.
+    pub max_funder_fanout: usize,
+    /// How long a buy waits for the analysis when its strategy filters on it.
+    pub wait: Duration,
+}
+
+impl InsiderConfig {
+    pub fn from_env() -> Self {
+        let enabled = env::var("insider_detection")
+            .map(|v| v.parse::<bool>().expect("insider_detection must be true or false"))
+            .unwrap_or(false);
+
+        let funding_scan_limit = env::var("insider_scan_limit")
+            .map(|v| v.parse::<usize>().expect("insider_scan_limit must be a valid usize"))
+            .unwrap_or(10);
+
+        let max_funder_fanout = env::var("insider_max_funder_fanout")
+            .map(|v| v.parse::<usize>().expect("insider_max_funder_fanout must be a valid usize"))
+            .unwrap_or(3);
+
+        let wait_ms = env::var("insider_wait_ms")
+            .map(|v| v.parse::<u64>().expect("insider_wait_ms must be a valid u64"))
+            .unwrap_or(1_500);
+
+        InsiderConfig {
+            enabled,
+            funding_scan_limit,
+            max_funder_fanout,
+            wait: Duration::from_millis(wait_ms),
+        }
+    }
+}
+
+/// Who bought a mint in its create slot and how much of the supply the
+/// creator and wallets tied to them hold.
+#[derive(Clone, Debug)]
+pub struct InsiderReport {
+    /// Distinct wallets, the creator aside, that bought in the create slot.
+    pub buyers: usize,
+    /// Those of them funded by the creator or sharing a funder with the
+    /// creator or with another buyer.
+    pub cluster: Vec<Pubkey>,
+    /// Share of the supply, in percent, held by the creator plus the cluster.
+    pub supply_pct: f64,
+}
+
+/// Runs the create-slot analysis for new mints in the background and keeps
+/// the results for the entry filter.
+pub struct InsiderDetector {
+    config: InsiderConfig,
+    state: Mutex<DetectorState>,
+}
+
+#[derive(Default)]
+struct DetectorState {
+    // `None` while the analysis is running
+    reports: HashMap<Pubkey, Option<InsiderReport>>,
+    order: VecDeque<Pubkey>,
+}
+
+impl InsiderDetector {
+    pub fn new(config: InsiderConfig) -> Self {
+        InsiderDetector {
+            config,
+            state: Mutex::new(DetectorState::default()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Marks `mint` as being analysed; false if it already was.
+    pub fn begin(&self, mint: Pubkey) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.reports.contains_key(&mint) {
+            return false;
+        }
+        state.reports.insert(mint, None);
+        state.order.push_back(mint);
+        while state.order.len() > MAX_LAUNCHES {
+            if let Some(old) = state.order.pop_front() {
+                state.reports.remove(&old);
+            }
+        }
+        true
+    }
+
+    /// The report for `mint`, waiting up to the configured time for it.
+    pub async fn wait_for(&self, mint: &Pubkey) -> Option<InsiderReport> {
+        let deadline = Instant::now() + self.config.wait;
+        loop {
+            match self.state.lock().unwrap().reports.get(mint) {
+                Some(Some(report)) => return Some(report.clone()),
+                Some(None) => {}
+                // not analysed at all, nothing to wait for
+                None => return None,
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Reads the create slot's block for buys of `mint`, falling back to the
+    /// create-slot buys seen on the log stream, then clusters the buyers by funding.
+    pub async fn analyse(
+        &self,
+        client: &RpcClient,
+        snipers: &SniperBoard,
+        mint: Pubkey,
+        creator: Pubkey,
+        create_slot: u64,
+        virtual_token_reserves: u64,
+    ) {
+        let mut bought: HashMap<Pubkey, u64> = HashMap::new();
+        match block_buys(client, create_slot, &mint).await {
+            Some(buys) => {
+                for (wallet, tokens) in buys {
+                    *bought.entry(wallet).or_default() += tokens;
+                }
+            }
+            None => {
+                for (wallet, tokens) in snipers.block0_buys(&mint) {
+                    *bought.entry(wallet).or_default() += tokens;
+                }
+            }
+        }
+        // the creator's buy in the create transaction is what left the curve
+        let creator_tokens = PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES.saturating_sub(virtual_token_reserves);
+        bought.remove(&creator);
+
+        let limit = self.config.funding_scan_limit;
+        let buyers: Vec<Pubkey> = bought.keys().copied().collect();
+        let (creator_funded, creator_funders, buyer_funders) = tokio::join!(
+            find_creator_funded_wallets(client, &creator, limit),
+            find_funders(client, &creator, limit),
+            join_all(buyers.iter().map(|wallet| find_funders(client, wallet, limit))),
+        );
+        let creator_funded: HashSet<Pubkey> = creator_funded.into_iter().collect();
+        let mut creator_funders: HashSet<Pubkey> = creator_funders.into_iter().collect();
+        let mut funders: HashMap<Pubkey, HashSet<Pubkey>> = buyers
+            .into_iter()
+            .zip(buyer_funders)
+            .map(|(wallet, theirs)| (wallet, theirs.into_iter().collect()))
+            .collect();
+
+        // a funder behind many buyers is an exchange or a faucet, not a team
+        let mut fanout: HashMap<Pubkey, usize> = HashMap::new();
+        for funder in funders.values().flatten() {
+            *fanout.entry(*funder).or_default() += 1;
+        }
+        let busy: HashSet<Pubkey> = fanout
+            .into_iter()
+            .filter(|(_, count)| *count > self.config.max_funder_fanout)
+            .map(|(funder, _)| funder)
+            .collect();
+        creator_funders.retain(|funder| !busy.contains(funder));
+        for theirs in funders.values_mut() {
+            theirs.retain(|funder| !busy.contains(funder));
+        }
+
+        let cluster: Vec<Pubkey> = bought
+            .keys()
+            .filter(|wallet| {
+                let own = &funders[*wallet];
+                creator_funded.contains(*wallet)
+                    || own.contains(&creator)
+                    || !own.is_disjoint(&creator_funders)
+                    || funders.iter().any(|(other, theirs)| other != *wallet && !own.is_disjoint(theirs))
+            })
+            .copied()
+            .collect();
+
+        let cluster_tokens = creator_tokens + cluster.iter().map(|w| bought[w]).sum::<u64>();
+        let report = InsiderReport {
+            buyers: bought.len(),
+            cluster,
+            supply_pct: cluster_tokens as f64 / PUMPFUN_TOTAL_SUPPLY as f64 * 100.0,
+        };
+
+        if !report.cluster.is_empty() {
+            println!(
+                "{}:: {} create slot: {} buyers, {} tied to the creator, insiders hold {:.1}% of supply",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                mint,
+                report.buyers,
+                report.cluster.len(),
+                report.supply_pct
+            );
+        }
+        // the launch may have been evicted while we were at it
+        if let Some(entry) = self.state.lock().unwrap().reports.get_mut(&mint) {
+            *entry = Some(report);
+        }
+    }
+}
+
+// (buyer, tokens) for every buy of `mint` in the block, `None` if the block could not be read
+async fn block_buys(client: &RpcClient, slot: u64, mint: &Pubkey) -> Option<Vec<(Pubkey, u64)>> {
+    let config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        transaction_details: Some(TransactionDetails::Full),
+        rewards: Some(false),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for _ in 0..BLOCK_ATTEMPTS {
+        let block = match client.get_block_with_config(slot, config).await {
+            Ok(block) => block,
+            Err(_) => {
+                sleep(BLOCK_DELAY).await;
+                continue;
+            }
+        };
+
+        let mut buys = Vec::new();
+        for tx in block.transactions.unwrap_or_default() {
+            let meta = match tx.meta {
+                Some(meta) if meta.err.is_none() => meta,
+                _ => continue,
+            };
+            let logs: Option<Vec<String>> = meta.log_messages.into();
+            for trade in decode_trade_events(&logs.unwrap_or_default()) {
+                if trade.mint == *mint && trade.is_buy_bool() {
+                    buys.push((trade.user, trade.token_amount));
+                }
+            }
+        }
+        return Some(buys);
+    }
+
+    None
+}
@@ -2,6 +2,8 @@ use solana_sdk::pubkey::Pubkey;
 
 use anchor_lang::prelude::*;
 
+use super::constants::*;
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct AmmInfoV4 {
     pub status: u64,
@@ -177,11 +179,33 @@ impl TradeEvent {
         self.virtual_token_reserves
     }
 
-    /// Decodes from bytes, ensuring proper type management.
-    #[inline(never)]
-    pub fn decode_trade_event(mut trade_event_data: &[u8]) -> Self {
-        let deserialized = Self::deserialize(&mut trade_event_data).unwrap();
-        deserialized
+    /// SOL actually deposited in the curve, derived from the virtual reserves.
+    pub fn get_real_sol_reserves(&self) -> u64 {
+        self.virtual_sol_reserves
+            .saturating_sub(PUMPFUN_INITIAL_VIRTUAL_SOL_RESERVES)
+    }
+
+    /// Decodes from bytes, returning `None` on malformed data.
+    pub fn try_decode_trade_event(mut trade_event_data: &[u8]) -> Option<Self> {
+        Self::deserialize(&mut trade_event_data).ok()
+    }
+}
+
+// create event
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CreateEvent {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub user: Pubkey,
+}
+
+impl CreateEvent {
+    /// Decodes from bytes, returning `None` on malformed data.
+    pub fn try_decode_create_event(mut create_event_data: &[u8]) -> Option<Self> {
+        Self::deserialize(&mut create_event_data).ok()
     }
 }
 
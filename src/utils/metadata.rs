@@ -0,0 +1,393 @@
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::strategy::CreateInfo;
+
+// URIs whose documents we keep, oldest are forgotten first
+const MAX_CACHED: usize = 5_000;
+// launches whose fetch results we keep for the entry check
+const MAX_LAUNCHES: usize = 2_000;
+// metadata documents are a few hundred bytes, anything past this is cut off
+const MAX_DOCUMENT_BYTES: usize = 64 * 1024;
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct MetadataConfig {
+    /// Fetch the URI document of every launch.
+    pub fetch: bool,
+    pub fetch_timeout: Duration,
+    /// Gateway used for `ipfs://` URIs.
+    pub ipfs_gateway: String,
+    /// Name or symbol must contain one of these, when any are given.
+    pub allow: Vec<String>,
+    /// Name, symbol or description must contain none of these.
+    pub deny: Vec<String>,
+    pub name_regex: Option<Regex>,
+    /// Reject names already used by this many of the most recent launches, 0 keeps duplicates.
+    pub duplicate_window: usize,
+    pub require_social: bool,
+    pub require_image: bool,
+}
+
+// comma separated, lowercased, empty entries dropped
+fn keywords(key: &str) -> Vec<String> {
+    env::var(key)
+        .map(|v| {
+            v.split(',')
+                .map(|k| k.trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl MetadataConfig {
+    pub fn from_env() -> Self {
+        let fetch = env::var("metadata_fetch")
+            .map(|v| v.parse::<bool>().expect("metadata_fetch must be true or false"))
+            .unwrap_or(false);
+
+        let timeout_ms = env::var("metadata_timeout_ms")
+            .map(|v| v.parse::<u64>().expect("metadata_timeout_ms must be a valid u64"))
+            .unwrap_or(1_500);
+
+        let name_regex = env::var("meta_name_regex")
+            .ok()
+            .map(|v| Regex::new(&v).expect("meta_name_regex must be a valid regex"));
+
+        let duplicate_window = env::var("meta_duplicate_window")
+            .map(|v| v.parse::<usize>().expect("meta_duplicate_window must be a valid usize"))
+            .unwrap_or(0);
+
+        let require_social = env::var("meta_require_social")
+            .map(|v| v.parse::<bool>().expect("meta_require_social must be true or false"))
+            .unwrap_or(false);
+
+        let require_image = env::var("meta_require_image")
+            .map(|v| v.parse::<bool>().expect("meta_require_image must be true or false"))
+            .unwrap_or(false);
+
+        MetadataConfig {
+            fetch: fetch || require_social || require_image,
+            fetch_timeout: Duration::from_millis(timeout_ms),
+            ipfs_gateway: env::var("ipfs_gateway").unwrap_or("https://ipfs.io/ipfs/".to_string()),
+            allow: keywords("meta_allow"),
+            deny: keywords("meta_deny"),
+            name_regex,
+            duplicate_window,
+            require_social,
+            require_image,
+        }
+    }
+}
+
+/// What the URI document of a launch says about it.
+#[derive(Clone, Debug, Default)]
+pub struct TokenMetadata {
+    pub description: String,
+    pub image: bool,
+    pub twitter: Option<String>,
+    pub telegram: Option<String>,
+    pub website: Option<String>,
+}
+
+impl TokenMetadata {
+    pub fn from_json(doc: &Value) -> Self {
+        let link = |key: &str| {
+            doc[key]
+                .as_str()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        TokenMetadata {
+            description: doc["description"].as_str().unwrap_or_default().to_string(),
+            image: link("image").is_some(),
+            twitter: link("twitter"),
+            telegram: link("telegram"),
+            website: link("website"),
+        }
+    }
+
+    pub fn socials(&self) -> usize {
+        [&self.twitter, &self.telegram, &self.website]
+            .iter()
+            .filter(|link| link.is_some())
+            .count()
+    }
+}
+
+#[derive(Default)]
+struct FilterState {
+    // uri -> fetched document, `None` if it could not be read
+    cache: HashMap<String, Option<TokenMetadata>>,
+    cache_order: VecDeque<String>,
+    // mint -> fetch result, `None` while the fetch is running
+    launches: HashMap<Pubkey, Option<Option<TokenMetadata>>>,
+    launch_order: VecDeque<Pubkey>,
+    recent_names: VecDeque<String>,
+}
+
+/// Filters launches on their name, symbol and URI document before any
+/// strategy sees them.
+pub struct MetadataFilter {
+    config: MetadataConfig,
+    http: reqwest::Client,
+    state: Mutex<FilterState>,
+}
+
+impl MetadataFilter {
+    pub fn new(config: MetadataConfig) -> Self {
+        // a redirect could lead anywhere, past the host check
+        let http = reqwest::Client::builder()
+            .timeout(config.fetch_timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("Failed to build HTTP client");
+        MetadataFilter {
+            config,
+            http,
+            state: Mutex::new(FilterState::default()),
+        }
+    }
+
+    pub fn fetches(&self) -> bool {
+        self.config.fetch
+    }
+
+    /// Checks name and symbol, and records the name for duplicate detection.
+    /// Returns why the launch is rejected, if it is.
+    pub fn check_names(&self, create: &CreateInfo) -> Result<(), String> {
+        let name = create.name.trim().to_lowercase();
+        let symbol = create.symbol.trim().to_lowercase();
+
+        let duplicate = {
+            let mut state = self.state.lock().unwrap();
+            let duplicate = self.config.duplicate_window > 0 && state.recent_names.contains(&name);
+            if self.config.duplicate_window > 0 {
+                state.recent_names.push_back(name.clone());
+                while state.recent_names.len() > self.config.duplicate_window {
+                    state.recent_names.pop_front();
+                }
+            }
+            duplicate
+        };
+
+        if !self.config.allow.is_empty()
+            && !self.config.allow.iter().any(|k| name.contains(k) || symbol.contains(k))
+        {
+            return Err("name matches no allowed keyword".to_string());
+        }
+        if let Some(k) = self.config.deny.iter().find(|k| name.contains(*k) || symbol.contains(*k)) {
+            return Err(format!("name contains denied keyword {:?}", k));
+        }
+        if let Some(regex) = &self.config.name_regex {
+            if !regex.is_match(&create.name) {
+                return Err(format!("name {:?} does not match {}", create.name, regex));
+            }
+        }
+        if duplicate {
+            return Err(format!("name {:?} was used by a recent launch", create.name));
+        }
+        Ok(())
+    }
+
+    /// Checks a fetched URI document; `None` means it could not be read.
+    pub fn check_metadata(&self, metadata: Option<&TokenMetadata>) -> Result<(), String> {
+        let metadata = match metadata {
+            Some(metadata) => metadata,
+            None if self.config.require_social || self.config.require_image => {
+                return Err("metadata could not be fetched".to_string())
+            }
+            None => return Ok(()),
+        };
+
+        let description = metadata.description.to_lowercase();
+        if let Some(k) = self.config.deny.iter().find(|k| description.contains(*k)) {
+            return Err(format!("description contains denied keyword {:?}", k));
+        }
+        if self.config.require_social && metadata.socials() == 0 {
+            return Err("no social links".to_string());
+        }
+        if self.config.require_image && !metadata.image {
+            return Err("no image".to_string());
+        }
+        Ok(())
+    }
+
+    /// Marks `mint` as being fetched; false if it already was.
+    pub fn begin(&self, mint: Pubkey) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.launches.contains_key(&mint) {
+            return false;
+        }
+        state.launches.insert(mint, None);
+        state.launch_order.push_back(mint);
+        while state.launch_order.len() > MAX_LAUNCHES {
+            if let Some(old) = state.launch_order.pop_front() {
+                state.launches.remove(&old);
+            }
+        }
+        true
+    }
+
+    /// Fetches the URI document for `mint`, from the cache when the URI was seen before.
+    pub async fn fetch(&self, mint: Pubkey, uri: &str) {
+        let cached = self.state.lock().unwrap().cache.get(uri).cloned();
+        let metadata = match cached {
+            Some(metadata) => metadata,
+            None => {
+                let metadata = self.fetch_uri(uri).await;
+                let mut state = self.state.lock().unwrap();
+                state.cache.insert(uri.to_string(), metadata.clone());
+                state.cache_order.push_back(uri.to_string());
+                while state.cache_order.len() > MAX_CACHED {
+                    if let Some(old) = state.cache_order.pop_front() {
+                        state.cache.remove(&old);
+                    }
+                }
+                metadata
+            }
+        };
+
+        if let Some(entry) = self.state.lock().unwrap().launches.get_mut(&mint) {
+            *entry = Some(metadata);
+        }
+    }
+
+    async fn fetch_uri(&self, uri: &str) -> Option<TokenMetadata> {
+        // the gateway is ours, anything else comes from the launch and must be public
+        let url = match uri.strip_prefix("ipfs://") {
+            Some(cid) => format!("{}{}", self.config.ipfs_gateway, cid),
+            None => match public_https_url(uri).await {
+                Ok(()) => uri.to_string(),
+                Err(e) => {
+                    eprintln!("Not fetching metadata from {}: {}", uri, e);
+                    return None;
+                }
+            },
+        };
+        let body = match self.http.get(&url).send().await.and_then(|r| r.error_for_status()) {
+            Ok(response) => read_capped(response, MAX_DOCUMENT_BYTES).await,
+            Err(e) => Err(e),
+        };
+        match body.map(|body| serde_json::from_slice::<Value>(&body)) {
+            Ok(Ok(doc)) => Some(TokenMetadata::from_json(&doc)),
+            Ok(Err(e)) => {
+                eprintln!("Metadata at {} is not JSON: {:?}", url, e);
+                None
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch metadata from {}: {:?}", url, e);
+                None
+            }
+        }
+    }
+
+    /// The fetch result for `mint`, waiting up to the fetch timeout for it.
+    /// `None` if it was never fetched or did not finish in time.
+    pub async fn wait_for(&self, mint: &Pubkey) -> Option<Option<TokenMetadata>> {
+        let deadline = Instant::now() + self.config.fetch_timeout;
+        loop {
+            match self.state.lock().unwrap().launches.get(mint) {
+                Some(Some(metadata)) => return Some(metadata.clone()),
+                Some(None) => {}
+                None => return None,
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+// Ok when `uri` is https and every address its host resolves to is public
+async fn public_https_url(uri: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(uri).map_err(|e| e.to_string())?;
+    if url.scheme() != "https" {
+        return Err(format!("{} URIs are not fetched", url.scheme()));
+    }
+    let host = url.host_str().ok_or("no host")?;
+    let addrs = tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(443)))
+        .await
+        .map_err(|e| e.to_string())?;
+    for addr in addrs {
+        if !is_public(addr.ip()) {
+            return Err(format!("{} resolves to {}", host, addr.ip()));
+        }
+    }
+    Ok(())
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // unique local and link-local
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// the body, or as much of it as fits in `limit` bytes
+async fn read_capped(mut response: reqwest::Response, limit: usize) -> reqwest::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = limit - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if body.len() >= limit {
+            break;
+        }
+    }
+    Ok(body)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn only_public_https_uris_are_fetched() {
+        assert!(public_https_url("http://8.8.8.8/meta.json").await.is_err());
+        assert!(public_https_url("file:///etc/passwd").await.is_err());
+        assert!(public_https_url("https://127.0.0.1/meta.json").await.is_err());
+        assert!(public_https_url("https://10.0.0.5/meta.json").await.is_err());
+        assert!(public_https_url("https://169.254.169.254/latest").await.is_err());
+        assert!(public_https_url("https://[::1]/meta.json").await.is_err());
+        assert!(public_https_url("https://8.8.8.8/meta.json").await.is_ok());
+    }
+
+    #[test]
+    fn private_ranges_are_not_public() {
+        for ip in ["192.168.1.1", "172.16.0.1", "100.64.0.1", "0.0.0.0", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_public("1.1.1.1".parse().unwrap()));
+        assert!(is_public("2606:4700::1111".parse().unwrap()));
+    }
+}
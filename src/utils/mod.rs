@@ -1,5 +1,24 @@
+pub mod bus;
+pub mod candles;
 pub mod check_logs_buy_sell;
 pub mod constants;
+pub mod control;
 pub mod create_ix;
+pub mod holders;
+pub mod insiders;
 pub mod layouts;
+pub mod metadata;
+pub mod pf_events;
 pub mod pf_price;
+pub mod pipeline;
+pub mod positions;
+pub mod recorder;
+pub mod replay;
+pub mod risk;
+pub mod rug_watch;
+pub mod slots;
+pub mod shutdown;
+pub mod snipers;
+#[cfg(test)]
+pub mod testing;
+pub mod token_balance;
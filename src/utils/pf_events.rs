@@ -0,0 +1,49 @@
+use super::constants::*;
+use super::layouts::{CreateEvent, TradeEvent};
+
+#[derive(Clone, Debug)]
+pub enum PumpEvent {
+    Create(CreateEvent),
+    Trade(TradeEvent),
+}
+
+/// Decodes every pump.fun `Program data:` entry in a log batch, in log order.
+/// Entries that don't carry a known event discriminator are skipped.
+pub fn decode_pump_events(logs: &[String]) -> Vec<PumpEvent> {
+    let mut events = Vec::new();
+
+    for log in logs {
+        let log_data = match log.strip_prefix("Program data: ") {
+            Some(data) => data,
+            None => continue,
+        };
+        let log_decoded = match base64::decode(log_data) {
+            Ok(bytes) if bytes.len() > 8 => bytes,
+            _ => continue,
+        };
+
+        let (discriminator, body) = log_decoded.split_at(8);
+        if discriminator == CREATE_EVENT_DISCRIMINATOR {
+            if let Some(create_event) = CreateEvent::try_decode_create_event(body) {
+                events.push(PumpEvent::Create(create_event));
+            }
+        } else if discriminator == TRADE_EVENT_DISCRIMINATOR {
+            if let Some(trade_event) = TradeEvent::try_decode_trade_event(body) {
+                events.push(PumpEvent::Trade(trade_event));
+            }
+        }
+    }
+
+    events
+}
+
+/// Convenience filter for callers that only care about trades.
+pub fn decode_trade_events(logs: &[String]) -> Vec<TradeEvent> {
+    decode_pump_events(logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            PumpEvent::Trade(trade_event) => Some(trade_event),
+            _ => None,
+        })
+        .collect()
+}
@@ -1,5 +1,7 @@
 use std::ops::{Div, Mul};
 
+use super::constants::PUMPFUN_FEE_BASIS_POINTS;
+
 pub async fn get_sol2tokens(
     virtual_sol_reserves: u64,
     virtual_token_reserves: u64,
@@ -34,6 +36,46 @@ pub async fn get_sol2tokens(
     Ok(final_with_slippage_int)
 }
 
+/// The pump.fun fee on a buy putting `sol_in` lamports into the curve. It is
+/// paid on top of `sol_in`.
+pub fn buy_fee(sol_in: u64) -> u64 {
+    (sol_in as u128 * PUMPFUN_FEE_BASIS_POINTS as u128 / 10_000) as u64
+}
+
+/// Exact bonding curve quote: tokens received for `sol_in` lamports going
+/// into the curve, the fee aside. Mirrors the program's integer math.
+pub fn curve_buy_tokens(virtual_sol_reserves: u64, virtual_token_reserves: u64, sol_in: u64) -> u64 {
+    let v_sol = virtual_sol_reserves as u128;
+    let v_tokens = virtual_token_reserves as u128;
+    if v_sol == 0 || v_tokens == 0 {
+        return 0;
+    }
+
+    let product = v_sol * v_tokens;
+    let new_v_sol = v_sol + sol_in as u128;
+    let new_v_tokens = product / new_v_sol + 1;
+
+    v_tokens.saturating_sub(new_v_tokens) as u64
+}
+
+/// Exact bonding curve quote: lamports received for selling `tokens_in`, net of the pump.fun fee.
+pub fn curve_sell_sol(virtual_sol_reserves: u64, virtual_token_reserves: u64, tokens_in: u64) -> u64 {
+    let v_sol = virtual_sol_reserves as u128;
+    let v_tokens = virtual_token_reserves as u128;
+    if v_sol == 0 || v_tokens == 0 {
+        return 0;
+    }
+
+    let product = v_sol * v_tokens;
+    let new_v_tokens = v_tokens + tokens_in as u128;
+    let new_v_sol = product / new_v_tokens + 1;
+
+    let sol_out = v_sol.saturating_sub(new_v_sol);
+    let fee = sol_out * PUMPFUN_FEE_BASIS_POINTS as u128 / 10_000;
+
+    (sol_out - fee) as u64
+}
+
 // let _completed = bc_decoded.complete_bool();
 // let price = vSOL as f64 / vTokens as f64;
 
@@ -42,3 +84,27 @@ pub async fn get_sol2tokens(
 // println!("Is complete: {}", is_complete);
 
 // println!("Price: {}", &price);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V_SOL: u64 = 30_000_000_000;
+    const V_TOKENS: u64 = 1_073_000_000_000_000;
+
+    #[test]
+    fn a_buy_puts_all_of_sol_in_into_the_curve() {
+        // 30e9 * 1.073e15 / 31e9 = 1_038_387_096_774_193.5, floored, plus one
+        assert_eq!(curve_buy_tokens(V_SOL, V_TOKENS, 1_000_000_000), 34_612_903_225_806);
+        assert_eq!(buy_fee(1_000_000_000), 10_000_000);
+        assert_eq!(curve_buy_tokens(0, V_TOKENS, 1_000_000_000), 0);
+    }
+
+    #[test]
+    fn selling_back_returns_sol_in_less_rounding_and_the_fee() {
+        let tokens = curve_buy_tokens(V_SOL, V_TOKENS, 1_000_000_000);
+        let after = (31_000_000_000, 1_038_387_096_774_194);
+        // 999_999_999 out of the curve, 1% of it is the fee
+        assert_eq!(curve_sell_sol(after.0, after.1, tokens), 990_000_000);
+    }
+}
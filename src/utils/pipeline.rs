@@ -0,0 +1,515 @@
+use chrono::Local;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use solana_sdk::pubkey::Pubkey;
+use tokio::task::JoinHandle;
+
+use super::bus::Event;
+use super::check_logs_buy_sell::{execute_buy, execute_sell, TradeContext};
+use crate::strategy::{Intent, Side};
+
+/// Events seen per kind since start.
+#[derive(Default)]
+pub struct PipelineMetrics {
+    counts: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl PipelineMetrics {
+    pub fn snapshot(&self) -> BTreeMap<&'static str, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    pub fn summary(&self) -> String {
+        self.snapshot()
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Subscribes every stage to the context's bus. Call before the first
+/// message is ingested.
+pub fn spawn(ctx: &Arc<TradeContext>) -> Arc<PipelineMetrics> {
+    let bus = ctx.bus.clone();
+
+    let stage = ctx.clone();
+    bus.subscribe("filters", move |event| filters(&stage, event));
+    let stage = ctx.clone();
+    bus.subscribe("strategies", move |event| strategies(&stage, event));
+    let stage = ctx.clone();
+    bus.subscribe("risk", move |event| risk(&stage, event));
+    let stage = ctx.clone();
+    bus.subscribe("execution", move |event| execution(&stage, event));
+
+    if let Ok(dir) = env::var("journal_dir") {
+        let dir = PathBuf::from(dir);
+        bus.subscribe("journal", move |event| {
+            if let Err(e) = journal(&dir, &event) {
+                eprintln!("Failed to write journal: {:?}", e);
+            }
+            Vec::new()
+        });
+    }
+
+    let metrics = Arc::new(PipelineMetrics::default());
+    let counts = metrics.clone();
+    bus.subscribe("metrics", move |event| {
+        *counts.counts.lock().unwrap().entry(event.kind()).or_default() += 1;
+        Vec::new()
+    });
+
+    metrics
+}
+
+// starts the background lookups a buy will wait on, then gates the create on its name
+fn filters(ctx: &Arc<TradeContext>, event: Event) -> Vec<JoinHandle<()>> {
+    let create = match event {
+        Event::Create(create) => create,
+        _ => return Vec::new(),
+    };
+
+    if ctx.risk.is_denied(&create.creator) {
+        ctx.bus.publish(Event::Rejected {
+            mint: create.mint,
+            reason: format!("creator {} is denied", create.creator),
+        });
+        return Vec::new();
+    }
+
+    // the URI document is needed by the time a buy is sent, start on it now
+    if ctx.metadata.fetches() && !create.uri.is_empty() && ctx.metadata.begin(create.mint) {
+        let ctx = ctx.clone();
+        let (mint, uri) = (create.mint, create.uri.clone());
+        tokio::spawn(async move {
+            ctx.metadata.fetch(mint, &uri).await;
+        });
+    }
+    if ctx.insiders.enabled() && ctx.executor.is_live() && create.slot > 0 && ctx.insiders.begin(create.mint) {
+        let ctx = ctx.clone();
+        let create = create.clone();
+        tokio::spawn(async move {
+            ctx.insiders
+                .analyse(
+                    &ctx.client,
+                    &ctx.snipers,
+                    create.mint,
+                    create.creator,
+                    create.slot,
+                    create.virtual_token_reserves,
+                )
+                .await;
+        });
+    }
+
+    match ctx.metadata.check_names(&create) {
+        Ok(()) => ctx.bus.publish(Event::Candidate(create)),
+        Err(reason) => {
+            println!(
+                "{}:: skipping {} ({}): {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                create.mint,
+                create.symbol,
+                reason
+            );
+            ctx.bus.publish(Event::Rejected {
+                mint: create.mint,
+                reason,
+            });
+        }
+    }
+    Vec::new()
+}
+
+// runs the strategy hooks; whatever the runner admits goes back on the bus
+fn strategies(ctx: &Arc<TradeContext>, event: Event) -> Vec<JoinHandle<()>> {
+    let mut handles = Vec::new();
+    let intents = match event {
+        Event::Candidate(create) => ctx.strategies.on_create(&create),
+        Event::Trade { trade, held } => {
+            if held && ctx.holders.enabled() && ctx.executor.is_live() && ctx.holders.on_trade(&trade.mint)
+            {
+                handles.push(refresh_holders(ctx, trade.mint));
+            }
+            ctx.strategies.on_trade(&trade, &ctx.candle_store)
+        }
+        Event::Fill { strategy, fill } => ctx.strategies.on_fill(strategy, &fill),
+        Event::SellFailed { strategy, mint } => {
+            ctx.strategies.on_sell_failed(strategy, &mint, ctx.executor.now_ms())
+        }
+        _ => Vec::new(),
+    };
+    for (strategy, intent) in intents {
+        ctx.bus.publish(Event::Intent { strategy, intent });
+    }
+    handles
+}
+
+// refreshes a held mint's distribution and runs the strategies' holder hooks
+fn refresh_holders(ctx: &Arc<TradeContext>, mint: Pubkey) -> JoinHandle<()> {
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let state = match ctx.positions.mint_state(&mint) {
+            Some(state) => state,
+            None => return,
+        };
+        let stats = ctx
+            .holders
+            .refresh(&ctx.client, &mint, &state.bonding_curve, &state.creator)
+            .await;
+        if let Some(stats) = stats {
+            for (strategy, intent) in ctx.strategies.on_holders(&mint, &stats) {
+                ctx.bus.publish(Event::Intent { strategy, intent });
+            }
+        }
+    })
+}
+
+// every holder of a rugged mint gets out on the emergency ladder
+fn risk(ctx: &Arc<TradeContext>, event: Event) -> Vec<JoinHandle<()>> {
+    if let Event::Trade { trade, .. } = event {
+        if ctx.rug_watch.on_trade(&trade).is_some() {
+            for strategy in ctx.positions.holders_of(&trade.mint) {
+                ctx.bus.publish(Event::EmergencySell {
+                    strategy,
+                    mint: trade.mint,
+                });
+            }
+        }
+    }
+    Vec::new()
+}
+
+fn execution(ctx: &Arc<TradeContext>, event: Event) -> Vec<JoinHandle<()>> {
+    let ctx = ctx.clone();
+    let handle = match event {
+        Event::Intent { strategy, intent } => match intent {
+            Intent::Buy { mint, lamports } => {
+                tokio::spawn(async move { execute_buy(&ctx, strategy, mint, lamports).await })
+            }
+            Intent::Sell { mint, pct } => {
+                tokio::spawn(async move { execute_sell(&ctx, strategy, mint, pct, false).await })
+            }
+            // cancels are settled by the runner and never published
+            Intent::Cancel { .. } => return Vec::new(),
+        },
+        Event::EmergencySell { strategy, mint } => {
+            tokio::spawn(async move { execute_sell(&ctx, strategy, mint, 1.0, true).await })
+        }
+        _ => return Vec::new(),
+    };
+    vec![handle]
+}
+
+// decisions and fills as JSONL, one file per day; raw messages are the recorder's job
+fn journal(dir: &PathBuf, event: &Event) -> std::io::Result<()> {
+    let now = Local::now();
+    let mut line = match event {
+        Event::Rejected { mint, reason } => json!({
+            "mint": mint.to_string(),
+            "reason": reason,
+        }),
+        Event::Intent { strategy, intent } => match intent {
+            Intent::Buy { mint, lamports } => json!({
+                "strategy": strategy,
+                "mint": mint.to_string(),
+                "buy_lamports": lamports,
+            }),
+            Intent::Sell { mint, pct } => json!({
+                "strategy": strategy,
+                "mint": mint.to_string(),
+                "sell_pct": pct,
+            }),
+            Intent::Cancel { .. } => return Ok(()),
+        },
+        Event::EmergencySell { strategy, mint } | Event::SellFailed { strategy, mint } => json!({
+            "strategy": strategy,
+            "mint": mint.to_string(),
+        }),
+        Event::Fill { strategy, fill } => json!({
+            "strategy": strategy,
+            "mint": fill.mint.to_string(),
+            "side": if fill.side == Side::Buy { "buy" } else { "sell" },
+            "token_amount": fill.token_amount,
+            "lamports": fill.lamports,
+            "time_ms": fill.time_ms,
+            "slot": fill.slot,
+        }),
+        _ => return Ok(()),
+    };
+
+    line["event"] = json!(event.kind());
+    line["at_ms"] = json!(now.timestamp_millis());
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("journal-{}.jsonl", now.format("%Y-%m-%d")));
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::copy_trade::CopyTrade;
+    use crate::strategy::runner::{EntryLimits, StrategyRunner};
+    use crate::strategy::{CreateInfo, Strategy};
+    use std::collections::HashSet;
+    use crate::utils::constants::{PUMPFUN_INITIAL_VIRTUAL_SOL_RESERVES, PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES};
+    use crate::utils::layouts::TradeEvent;
+    use crate::utils::check_logs_buy_sell::run_timers;
+    use crate::utils::positions::{MintState, Position};
+    use crate::utils::testing::{sim_context, snipe_and_hold};
+
+    fn create() -> CreateInfo {
+        CreateInfo {
+            mint: Pubkey::new_unique(),
+            bonding_curve: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            virtual_sol_reserves: PUMPFUN_INITIAL_VIRTUAL_SOL_RESERVES,
+            virtual_token_reserves: PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES,
+            slot: 0,
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            uri: String::new(),
+        }
+    }
+
+    fn creator_sell(create: &CreateInfo) -> TradeEvent {
+        TradeEvent {
+            mint: create.mint,
+            sol_amount: 1_000_000_000,
+            token_amount: 30_000_000_000_000,
+            is_buy: false,
+            user: create.creator,
+            timestamp: 0,
+            virtual_sol_reserves: PUMPFUN_INITIAL_VIRTUAL_SOL_RESERVES,
+            virtual_token_reserves: PUMPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES,
+        }
+    }
+
+    // everything published on the bus from now on
+    fn published(ctx: &Arc<TradeContext>) -> Arc<Mutex<Vec<Event>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        ctx.bus.subscribe("test", move |event| {
+            seen.lock().unwrap().push(event);
+            Vec::new()
+        });
+        events
+    }
+
+    // runs a stage on `event` and waits for what it started
+    async fn drive(
+        ctx: &Arc<TradeContext>,
+        stage: fn(&Arc<TradeContext>, Event) -> Vec<JoinHandle<()>>,
+        event: Event,
+    ) {
+        for handle in stage(ctx, event) {
+            ctx.bus.track(handle);
+        }
+        ctx.bus.settled().await;
+    }
+
+    #[tokio::test]
+    async fn filters_pass_candidates_and_reject_denied_creators() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        let events = published(&ctx);
+
+        let (allowed, denied) = (create(), create());
+        ctx.risk.deny_creator(denied.creator);
+        drive(&ctx, filters, Event::Create(allowed.clone())).await;
+        drive(&ctx, filters, Event::Create(denied.clone())).await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Event::Candidate(c) if c.mint == allowed.mint));
+        assert!(matches!(&events[1], Event::Rejected { mint, .. } if *mint == denied.mint));
+    }
+
+    #[tokio::test]
+    async fn strategies_publish_what_the_runner_admits() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        let events = published(&ctx);
+
+        let create = create();
+        drive(&ctx, strategies, Event::Candidate(create.clone())).await;
+        drive(
+            &ctx,
+            strategies,
+            Event::Trade {
+                trade: creator_sell(&create),
+                held: false,
+            },
+        )
+        .await;
+
+        let intents: Vec<Intent> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Intent { strategy: 0, intent } => Some(intent.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            intents,
+            vec![Intent::Buy {
+                mint: create.mint,
+                lamports: 1_000_000
+            }]
+        );
+        // the creator sold before the buy went out, the runner settled the cancel itself
+        assert_eq!(ctx.strategies.take_pending(0, &create.mint), None);
+    }
+
+    #[tokio::test]
+    async fn copy_trade_follows_the_wallets_on_its_list() {
+        let strategy: Box<dyn Strategy> =
+            Box::new(CopyTrade::new("copy_trade#0".to_string(), 1_000_000, HashSet::new()));
+        let ctx = sim_context(StrategyRunner::new(vec![(strategy, u64::MAX, EntryLimits::default())])).await;
+        let events = published(&ctx);
+
+        let create = create();
+        let (copied, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(ctx.strategies.with_copy_list(0, |wallets| wallets.insert(copied)), Some(true));
+        let trade = |user, is_buy| Event::Trade {
+            trade: TradeEvent {
+                user,
+                is_buy,
+                ..creator_sell(&create)
+            },
+            held: false,
+        };
+        drive(&ctx, strategies, trade(other, true)).await;
+        drive(&ctx, strategies, trade(copied, true)).await;
+
+        let intents: Vec<Intent> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Intent { strategy: 0, intent } => Some(intent.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            intents,
+            vec![Intent::Buy {
+                mint: create.mint,
+                lamports: 1_000_000
+            }]
+        );
+        // the copied wallet sold before the buy went out
+        drive(&ctx, strategies, trade(copied, false)).await;
+        assert_eq!(ctx.strategies.take_pending(0, &create.mint), None);
+    }
+
+    #[tokio::test]
+    async fn risk_sells_every_holder_of_a_rugged_mint() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        let events = published(&ctx);
+
+        let create = create();
+        ctx.rug_watch.watch(create.mint, create.creator);
+        ctx.positions.add(Position {
+            strategy: 0,
+            wallet: 0,
+            mint: create.mint,
+            bonding_curve: create.bonding_curve,
+            creator: create.creator,
+            tokens: 1_000,
+            cost_lamports: 1_000_000,
+            opened_ms: 0,
+        });
+        drive(
+            &ctx,
+            risk,
+            Event::Trade {
+                trade: creator_sell(&create),
+                held: true,
+            },
+        )
+        .await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], Event::EmergencySell { strategy: 0, mint } if *mint == create.mint));
+    }
+
+    #[tokio::test]
+    async fn execution_publishes_a_fill_for_each_buy() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        let events = published(&ctx);
+
+        let create = create();
+        ctx.positions.track_mint(
+            create.mint,
+            MintState {
+                bonding_curve: create.bonding_curve,
+                creator: create.creator,
+                virtual_sol_reserves: create.virtual_sol_reserves,
+                virtual_token_reserves: create.virtual_token_reserves,
+                create_slot: create.slot,
+            },
+        );
+        // the runner holds the buy until execution takes it
+        drive(&ctx, strategies, Event::Candidate(create.clone())).await;
+        let intent = events.lock().unwrap().remove(0);
+        drive(&ctx, execution, intent).await;
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            Event::Fill { strategy: 0, fill } => {
+                assert_eq!(fill.mint, create.mint);
+                assert_eq!(fill.side, Side::Buy);
+                assert!(fill.token_amount > 0);
+            }
+            other => panic!("expected a fill, got {:?}", other),
+        }
+        assert_eq!(ctx.positions.holders_of(&create.mint), vec![0]);
+    }
+
+    #[tokio::test]
+    async fn a_rejected_sell_is_tried_again() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        spawn(&ctx);
+        let sim = ctx.executor.as_simulated().unwrap();
+
+        let create = create();
+        ctx.positions.track_mint(
+            create.mint,
+            MintState {
+                bonding_curve: create.bonding_curve,
+                creator: create.creator,
+                virtual_sol_reserves: create.virtual_sol_reserves,
+                virtual_token_reserves: create.virtual_token_reserves,
+                create_slot: create.slot,
+            },
+        );
+        ctx.bus.publish(Event::Create(create.clone()));
+        ctx.bus.settled().await;
+
+        // the hold ends at 10s and nobody takes the sell
+        sim.reject_next(1);
+        sim.set_clock_ms(10_000);
+        run_timers(&ctx);
+        ctx.bus.settled().await;
+        assert_eq!(sim.rejected().len(), 1);
+        assert_eq!(ctx.positions.holders_of(&create.mint), vec![0]);
+
+        sim.set_clock_ms(12_000);
+        run_timers(&ctx);
+        ctx.bus.settled().await;
+        let sides: Vec<String> = sim.fills().into_iter().map(|f| f.side).collect();
+        assert_eq!(sides, vec!["buy", "sell"]);
+        assert!(ctx.positions.holders_of(&create.mint).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+// mints whose curve we remember, oldest are forgotten first
+const MAX_KNOWN_MINTS: usize = 10_000;
+
+/// Latest known state of a token's bonding curve.
+#[derive(Clone, Debug)]
+pub struct MintState {
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    pub virtual_sol_reserves: u64,
+    pub virtual_token_reserves: u64,
+    /// Slot of the notification the create was seen in, 0 if unknown.
+    pub create_slot: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Position {
+    pub strategy: usize,
+    /// Index into the wallet pool of the wallet holding the tokens.
+    pub wallet: usize,
+    pub mint: Pubkey,
+    pub bonding_curve: Pubkey,
+    pub creator: Pubkey,
+    pub tokens: u64,
+    pub cost_lamports: u64,
+    pub opened_ms: i64,
+}
+
+struct Book {
+    positions: HashMap<(usize, Pubkey), Position>,
+    mints: HashMap<Pubkey, MintState>,
+    mint_order: VecDeque<Pubkey>,
+}
+
+/// Open positions per (strategy, mint), plus the curve state of recently created mints.
+pub struct PositionBook {
+    book: Mutex<Book>,
+}
+
+impl PositionBook {
+    pub fn new() -> Self {
+        PositionBook {
+            book: Mutex::new(Book {
+                positions: HashMap::new(),
+                mints: HashMap::new(),
+                mint_order: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn track_mint(&self, mint: Pubkey, state: MintState) {
+        let mut book = self.book.lock().unwrap();
+        if book.mints.insert(mint, state).is_none() {
+            book.mint_order.push_back(mint);
+        }
+        while book.mint_order.len() > MAX_KNOWN_MINTS {
+            if let Some(oldest) = book.mint_order.pop_front() {
+                book.mints.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn update_curve(&self, mint: &Pubkey, virtual_sol_reserves: u64, virtual_token_reserves: u64) {
+        if let Some(state) = self.book.lock().unwrap().mints.get_mut(mint) {
+            state.virtual_sol_reserves = virtual_sol_reserves;
+            state.virtual_token_reserves = virtual_token_reserves;
+        }
+    }
+
+    pub fn mint_state(&self, mint: &Pubkey) -> Option<MintState> {
+        self.book.lock().unwrap().mints.get(mint).cloned()
+    }
+
+    /// Adds a buy to the position, opening it if needed.
+    pub fn add(&self, position: Position) {
+        let mut book = self.book.lock().unwrap();
+        let key = (position.strategy, position.mint);
+        match book.positions.get_mut(&key) {
+            Some(existing) => {
+                existing.tokens += position.tokens;
+                existing.cost_lamports += position.cost_lamports;
+            }
+            None => {
+                book.positions.insert(key, position);
+            }
+        }
+    }
+
+    /// Replaces an estimated buy amount with what actually landed.
+    pub fn adjust_tokens(&self, strategy: usize, mint: &Pubkey, estimated: u64, actual: u64) {
+        if let Some(position) = self.book.lock().unwrap().positions.get_mut(&(strategy, *mint)) {
+            position.tokens = position.tokens.saturating_sub(estimated) + actual;
+        }
+    }
+
+    /// Takes back a buy that was added but never landed.
+    pub fn undo_add(&self, strategy: usize, mint: &Pubkey, tokens: u64, cost_lamports: u64) {
+        let mut book = self.book.lock().unwrap();
+        let key = (strategy, *mint);
+        if let Some(position) = book.positions.get_mut(&key) {
+            position.tokens = position.tokens.saturating_sub(tokens);
+            position.cost_lamports = position.cost_lamports.saturating_sub(cost_lamports);
+            if position.tokens == 0 {
+                book.positions.remove(&key);
+            }
+        }
+    }
+
+    pub fn get(&self, strategy: usize, mint: &Pubkey) -> Option<Position> {
+        self.book.lock().unwrap().positions.get(&(strategy, *mint)).cloned()
+    }
+
+    /// Removes `tokens` from the position and returns the cost basis they carried.
+    pub fn reduce(&self, strategy: usize, mint: &Pubkey, tokens: u64) -> u64 {
+        let mut book = self.book.lock().unwrap();
+        let key = (strategy, *mint);
+        let position = match book.positions.get_mut(&key) {
+            Some(position) => position,
+            None => return 0,
+        };
+
+        if tokens >= position.tokens {
+            let cost = position.cost_lamports;
+            book.positions.remove(&key);
+            return cost;
+        }
+
+        let cost = (position.cost_lamports as u128 * tokens as u128 / position.tokens as u128) as u64;
+        position.tokens -= tokens;
+        position.cost_lamports -= cost;
+        cost
+    }
+
+    pub fn holders_of(&self, mint: &Pubkey) -> Vec<usize> {
+        self.book
+            .lock()
+            .unwrap()
+            .positions
+            .keys()
+            .filter(|(_, m)| m == mint)
+            .map(|(strategy, _)| *strategy)
+            .collect()
+    }
+
+    /// Strategies whose position in `mint` sits in the same wallet's token account.
+    pub fn holders_in_wallet(&self, mint: &Pubkey, wallet: usize) -> Vec<usize> {
+        self.book
+            .lock()
+            .unwrap()
+            .positions
+            .values()
+            .filter(|p| p.mint == *mint && p.wallet == wallet)
+            .map(|p| p.strategy)
+            .collect()
+    }
+
+    pub fn all(&self) -> Vec<Position> {
+        self.book.lock().unwrap().positions.values().cloned().collect()
+    }
+}
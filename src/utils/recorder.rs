@@ -0,0 +1,139 @@
+use chrono::{Local, TimeZone};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+// lines buffered before a gzip member is cut regardless of the flush timer
+const MAX_BUFFERED_LINES: usize = 4_096;
+
+/// Writes every raw WebSocket notification, with its receive time, to
+/// `<dir>/ws-<date>.jsonl.gz`. Each flush appends a separate gzip member so a
+/// killed process never leaves an unreadable file.
+pub struct Recorder {
+    tx: mpsc::UnboundedSender<(i64, String)>,
+    writer: JoinHandle<()>,
+}
+
+impl Recorder {
+    pub fn spawn(dir: PathBuf) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let writer = tokio::spawn(write_loop(dir, rx));
+        Recorder { tx, writer }
+    }
+
+    pub fn record(&self, text: &str) {
+        let recv_ms = Local::now().timestamp_millis();
+        let _ = self.tx.send((recv_ms, text.to_string()));
+    }
+
+    /// Writes out what is still buffered and stops the writer.
+    pub async fn close(self) {
+        drop(self.tx);
+        let _ = self.writer.await;
+    }
+}
+
+async fn write_loop(dir: PathBuf, mut rx: mpsc::UnboundedReceiver<(i64, String)>) {
+    let mut buffer: Vec<(i64, String)> = Vec::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Some(line) => {
+                    buffer.push(line);
+                    if buffer.len() >= MAX_BUFFERED_LINES {
+                        flush_lines(&dir, &mut buffer);
+                    }
+                }
+                None => {
+                    flush_lines(&dir, &mut buffer);
+                    break;
+                }
+            },
+            _ = ticker.tick() => flush_lines(&dir, &mut buffer),
+        }
+    }
+}
+
+fn flush_lines(dir: &Path, buffer: &mut Vec<(i64, String)>) {
+    if buffer.is_empty() {
+        return;
+    }
+    if let Err(e) = write_member(dir, buffer) {
+        eprintln!("Failed to write recording: {:?}", e);
+    }
+    buffer.clear();
+}
+
+fn write_member(dir: &Path, lines: &[(i64, String)]) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    // lines are grouped by their own date so rotation happens exactly at midnight
+    let mut start = 0;
+    while start < lines.len() {
+        let day = file_day(lines[start].0);
+        let end = lines[start..]
+            .iter()
+            .position(|(recv_ms, _)| file_day(*recv_ms) != day)
+            .map(|offset| start + offset)
+            .unwrap_or(lines.len());
+
+        let path = dir.join(format!("ws-{}.jsonl.gz", day));
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut encoder = GzEncoder::new(file, Compression::fast());
+        for (recv_ms, msg) in &lines[start..end] {
+            writeln!(encoder, "{}", json!({ "recv_ms": recv_ms, "msg": msg }))?;
+        }
+        encoder.finish()?;
+
+        start = end;
+    }
+
+    Ok(())
+}
+
+fn file_day(recv_ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(recv_ms)
+        .single()
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Reads a recording (gzip or plain) back as `(recv_ms, raw message)` pairs.
+pub fn read_recording(path: &Path) -> std::io::Result<Vec<(i64, String)>> {
+    let file = File::open(path)?;
+    let reader: Box<dyn BufRead> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let mut messages = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed: Value = match serde_json::from_str(&line) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("Skipping malformed recording line: {:?}", e);
+                continue;
+            }
+        };
+        if let (Some(recv_ms), Some(msg)) = (parsed["recv_ms"].as_i64(), parsed["msg"].as_str()) {
+            messages.push((recv_ms, msg.to_string()));
+        }
+    }
+
+    Ok(messages)
+}
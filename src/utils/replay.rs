@@ -0,0 +1,103 @@
+use chrono::Local;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::check_logs_buy_sell::{handle_message, run_timers, TradeContext};
+use super::recorder::read_recording;
+
+/// Feeds recorded notifications through `handle_message` in order, letting the
+/// pipeline settle after each one. Gaps between
+/// messages are divided by `speed`; a speed of 0 replays as fast as possible.
+/// Strategy timers run on the recorded clock, so the outcome does not depend
+/// on the speed. Returns the number of messages replayed.
+pub async fn replay_files(
+    paths: &[PathBuf],
+    speed: f64,
+    ctx: Arc<TradeContext>,
+) -> std::io::Result<usize> {
+    let mut replayed = 0;
+    let mut last_recv_ms: Option<i64> = None;
+
+    for path in paths {
+        println!(
+            "{}:: replaying {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            path.display()
+        );
+
+        for (recv_ms, msg) in read_recording(path)? {
+            if let Some(last) = last_recv_ms {
+                let gap_ms = (recv_ms - last).max(0) as u64;
+                if speed > 0.0 && gap_ms > 0 {
+                    tokio::time::sleep(Duration::from_millis(gap_ms).div_f64(speed)).await;
+                }
+            }
+            last_recv_ms = Some(recv_ms);
+
+            if let Some(sim) = ctx.executor.as_simulated() {
+                sim.set_clock_ms(recv_ms);
+            }
+
+            // the simulated executor never waits on the network, so settling
+            // each message before the next keeps the run deterministic
+            handle_message(&msg, &ctx);
+            ctx.bus.settled().await;
+            run_timers(&ctx);
+            ctx.bus.settled().await;
+            replayed += 1;
+        }
+    }
+
+    // let every remaining hold expire
+    if let (Some(sim), Some(last)) = (ctx.executor.as_simulated(), last_recv_ms) {
+        sim.set_clock_ms(last + END_OF_REPLAY_MS);
+        run_timers(&ctx);
+        ctx.bus.settled().await;
+    }
+
+    Ok(replayed)
+}
+
+// how far the clock jumps after the last message to flush open positions
+const END_OF_REPLAY_MS: i64 = 24 * 60 * 60 * 1_000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::pipeline;
+    use crate::utils::testing::{sim_context, snipe_and_hold};
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[tokio::test]
+    async fn buys_the_create_and_sells_when_the_creator_dumps() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        pipeline::spawn(&ctx);
+
+        let replayed = replay_files(&[fixture("create_then_rug.jsonl")], 0.0, ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(replayed, 2);
+
+        let fills = ctx.executor.as_simulated().unwrap().fills();
+        let sides: Vec<(&str, i64)> = fills.iter().map(|f| (f.side.as_str(), f.recv_ms)).collect();
+        assert_eq!(sides, vec![("buy", 1_000), ("sell", 2_000)]);
+        assert!(fills.iter().all(|f| f.mint == fills[0].mint && f.token_amount > 0));
+        assert!(ctx.positions.all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_malformed_creates() {
+        let ctx = sim_context(snipe_and_hold()).await;
+        pipeline::spawn(&ctx);
+
+        let replayed = replay_files(&[fixture("malformed_creates.jsonl")], 0.0, ctx.clone())
+            .await
+            .unwrap();
+        assert_eq!(replayed, 3);
+        assert!(ctx.executor.as_simulated().unwrap().fills().is_empty());
+    }
+}
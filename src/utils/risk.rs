@@ -0,0 +1,276 @@
+use chrono::{Local, NaiveDate};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use super::constants::LAMPORTS_PER_SOL;
+use super::positions::PositionBook;
+
+/// Portfolio-wide limits, applied to every buy regardless of strategy. Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct RiskConfig {
+    pub max_positions: usize,
+    pub max_exposure_lamports: u64,
+    pub max_buys_per_creator: usize,
+    pub max_buys_per_minute: usize,
+    /// Realised loss for the day that halts new entries until reset.
+    pub daily_loss_limit_lamports: u64,
+    /// Creators whose launches are never bought. Can be changed at runtime.
+    pub deny_creators: Vec<Pubkey>,
+}
+
+fn sol_env(key: &str, default: f64) -> u64 {
+    let sol = env::var(key)
+        .map(|v| v.parse::<f64>().unwrap_or_else(|_| panic!("{} must be a valid f64", key)))
+        .unwrap_or(default);
+    (sol * LAMPORTS_PER_SOL as f64) as u64
+}
+
+impl RiskConfig {
+    pub fn from_env() -> Self {
+        let max_positions = env::var("max_positions")
+            .map(|v| v.parse::<usize>().expect("max_positions must be a valid usize"))
+            .unwrap_or(5);
+
+        let max_buys_per_creator = env::var("max_buys_per_creator")
+            .map(|v| v.parse::<usize>().expect("max_buys_per_creator must be a valid usize"))
+            .unwrap_or(1);
+
+        let max_buys_per_minute = env::var("max_buys_per_minute")
+            .map(|v| v.parse::<usize>().expect("max_buys_per_minute must be a valid usize"))
+            .unwrap_or(10);
+
+        let deny_creators = env::var("deny_creators")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|w| !w.is_empty())
+                    .map(|w| Pubkey::from_str(w).expect("deny_creators must be valid pubkeys"))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        RiskConfig {
+            max_positions,
+            max_exposure_lamports: sol_env("max_exposure", 1.0),
+            max_buys_per_creator,
+            max_buys_per_minute,
+            daily_loss_limit_lamports: sol_env("daily_loss_limit", 0.5),
+            deny_creators,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RiskRejection {
+    Halted,
+    Paused,
+    ShuttingDown,
+    DeniedCreator(Pubkey),
+    MaxPositions(usize),
+    MaxExposure { exposure: u64, requested: u64 },
+    CreatorLimit(Pubkey),
+    RateLimit(usize),
+}
+
+impl fmt::Display for RiskRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskRejection::Halted => write!(f, "daily loss limit hit"),
+            RiskRejection::Paused => write!(f, "entries paused"),
+            RiskRejection::ShuttingDown => write!(f, "shutting down"),
+            RiskRejection::DeniedCreator(creator) => write!(f, "creator {} is denied", creator),
+            RiskRejection::MaxPositions(open) => write!(f, "{} positions open or in flight", open),
+            RiskRejection::MaxExposure { exposure, requested } => {
+                write!(f, "{} lamports exposed, {} more requested", exposure, requested)
+            }
+            RiskRejection::CreatorLimit(creator) => write!(f, "buy limit reached for creator {}", creator),
+            RiskRejection::RateLimit(recent) => write!(f, "{} buys in the last minute", recent),
+        }
+    }
+}
+
+struct RiskState {
+    // buys that passed the check but have not landed yet
+    in_flight: HashMap<(usize, Pubkey), u64>,
+    creator_buys: HashMap<Pubkey, usize>,
+    recent_buys_ms: VecDeque<i64>,
+    day: NaiveDate,
+    realised_pnl_lamports: i64,
+    halted: bool,
+    // set by hand, lifted only by hand
+    paused: bool,
+    denied_creators: HashSet<Pubkey>,
+    // set once on shutdown, unlike a halt it survives midnight and `reset`
+    stopping: bool,
+}
+
+/// Central gate in front of every buy, shared by all strategies.
+pub struct RiskManager {
+    config: RiskConfig,
+    state: Mutex<RiskState>,
+}
+
+impl RiskManager {
+    pub fn new(config: RiskConfig) -> Self {
+        let denied_creators = config.deny_creators.iter().copied().collect();
+        RiskManager {
+            config,
+            state: Mutex::new(RiskState {
+                in_flight: HashMap::new(),
+                creator_buys: HashMap::new(),
+                recent_buys_ms: VecDeque::new(),
+                day: Local::now().date_naive(),
+                realised_pnl_lamports: 0,
+                halted: false,
+                paused: false,
+                denied_creators,
+                stopping: false,
+            }),
+        }
+    }
+
+    /// Checks every limit and, if the buy is allowed, reserves it as in flight.
+    pub fn check_buy(
+        &self,
+        positions: &PositionBook,
+        strategy: usize,
+        mint: &Pubkey,
+        creator: &Pubkey,
+        lamports: u64,
+        now_ms: i64,
+    ) -> Result<(), RiskRejection> {
+        let mut state = self.state.lock().unwrap();
+        self.roll_day(&mut state);
+
+        if state.stopping {
+            return Err(RiskRejection::ShuttingDown);
+        }
+        if state.halted {
+            return Err(RiskRejection::Halted);
+        }
+        if state.paused {
+            return Err(RiskRejection::Paused);
+        }
+        if state.denied_creators.contains(creator) {
+            return Err(RiskRejection::DeniedCreator(*creator));
+        }
+
+        let open = positions.all();
+        let position_count = open.len() + state.in_flight.len();
+        if position_count >= self.config.max_positions {
+            return Err(RiskRejection::MaxPositions(position_count));
+        }
+
+        let exposure = open.iter().map(|p| p.cost_lamports).sum::<u64>()
+            + state.in_flight.values().sum::<u64>();
+        if exposure + lamports > self.config.max_exposure_lamports {
+            return Err(RiskRejection::MaxExposure {
+                exposure,
+                requested: lamports,
+            });
+        }
+
+        if state.creator_buys.get(creator).copied().unwrap_or(0) >= self.config.max_buys_per_creator {
+            return Err(RiskRejection::CreatorLimit(*creator));
+        }
+
+        while let Some(ts) = state.recent_buys_ms.front() {
+            if now_ms - ts > 60_000 {
+                state.recent_buys_ms.pop_front();
+            } else {
+                break;
+            }
+        }
+        if state.recent_buys_ms.len() >= self.config.max_buys_per_minute {
+            return Err(RiskRejection::RateLimit(state.recent_buys_ms.len()));
+        }
+
+        state.in_flight.insert((strategy, *mint), lamports);
+        *state.creator_buys.entry(*creator).or_insert(0) += 1;
+        state.recent_buys_ms.push_back(now_ms);
+        Ok(())
+    }
+
+    /// The buy resolved, landed or not; it is no longer in flight.
+    pub fn on_buy_done(&self, strategy: usize, mint: &Pubkey) {
+        self.state.lock().unwrap().in_flight.remove(&(strategy, *mint));
+    }
+
+    /// Books realised PnL for a sell and halts entries once the daily limit is hit.
+    pub fn on_sell(&self, cost_lamports: u64, proceeds_lamports: u64) {
+        let mut state = self.state.lock().unwrap();
+        self.roll_day(&mut state);
+
+        state.realised_pnl_lamports += proceeds_lamports as i64 - cost_lamports as i64;
+        if !state.halted && -state.realised_pnl_lamports >= self.config.daily_loss_limit_lamports as i64 {
+            state.halted = true;
+            println!(
+                "{}:: daily loss limit hit ({} lamports), halting new entries",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                -state.realised_pnl_lamports
+            );
+        }
+    }
+
+    /// Refuses every buy from now on, for shutdown.
+    pub fn stop_entries(&self) {
+        self.state.lock().unwrap().stopping = true;
+    }
+
+    /// Holds new entries until resumed. Open positions are still managed.
+    pub fn set_paused(&self, paused: bool) {
+        self.state.lock().unwrap().paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    /// Adds a creator to the deny list. `false` if it was already on it.
+    pub fn deny_creator(&self, creator: Pubkey) -> bool {
+        self.state.lock().unwrap().denied_creators.insert(creator)
+    }
+
+    /// Takes a creator off the deny list. `false` if it was not on it.
+    pub fn allow_creator(&self, creator: &Pubkey) -> bool {
+        self.state.lock().unwrap().denied_creators.remove(creator)
+    }
+
+    pub fn is_denied(&self, creator: &Pubkey) -> bool {
+        self.state.lock().unwrap().denied_creators.contains(creator)
+    }
+
+    pub fn denied_creators(&self) -> Vec<Pubkey> {
+        self.state.lock().unwrap().denied_creators.iter().copied().collect()
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.state.lock().unwrap().halted
+    }
+
+    pub fn realised_pnl_lamports(&self) -> i64 {
+        self.state.lock().unwrap().realised_pnl_lamports
+    }
+
+    /// Clears the daily loss and lifts a halt. Also happens automatically at local midnight.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.realised_pnl_lamports = 0;
+        state.halted = false;
+        state.creator_buys.clear();
+    }
+
+    fn roll_day(&self, state: &mut RiskState) {
+        let today = Local::now().date_naive();
+        if today != state.day {
+            state.day = today;
+            state.realised_pnl_lamports = 0;
+            state.halted = false;
+            state.creator_buys.clear();
+        }
+    }
+}
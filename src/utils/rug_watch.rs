@@ -0,0 +1,275 @@
+use chrono::Local;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fmt;
+use std::sync::Mutex;
+
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config, rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+    system_instruction::SystemInstruction, system_program,
+};
+use solana_transaction_status::UiTransactionEncoding;
+use std::str::FromStr;
+
+use super::layouts::TradeEvent;
+
+/// Thresholds for the emergency exit. Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct RugConfig {
+    /// A single sell taking more than this fraction of the curve's real SOL triggers an exit.
+    pub max_sell_pct: f64,
+    /// Fractional drop of virtual SOL reserves from the window peak that triggers an exit.
+    pub drop_pct: f64,
+    /// Window, in seconds of trade timestamps, used for the reserve drop check.
+    pub drop_window_secs: i64,
+    /// How many of the creator's recent transactions to scan for funded wallets.
+    pub creator_scan_limit: usize,
+}
+
+impl RugConfig {
+    pub fn from_env() -> Self {
+        let max_sell_pct = env::var("rug_max_sell_pct")
+            .map(|v| v.parse::<f64>().expect("rug_max_sell_pct must be a valid f64"))
+            .unwrap_or(0.25);
+
+        let drop_pct = env::var("rug_drop_pct")
+            .map(|v| v.parse::<f64>().expect("rug_drop_pct must be a valid f64"))
+            .unwrap_or(0.3);
+
+        let drop_window_secs = env::var("rug_drop_window")
+            .map(|v| v.parse::<i64>().expect("rug_drop_window must be a valid i64"))
+            .unwrap_or(5);
+
+        let creator_scan_limit = env::var("rug_creator_scan")
+            .map(|v| v.parse::<usize>().expect("rug_creator_scan must be a valid usize"))
+            .unwrap_or(25);
+
+        RugConfig {
+            max_sell_pct,
+            drop_pct,
+            drop_window_secs,
+            creator_scan_limit,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RugSignal {
+    CreatorSell,
+    LinkedWalletSell(Pubkey),
+    LargeSell { sol_amount: u64, real_sol_reserves: u64 },
+    ReserveDrop { peak: u64, now: u64 },
+}
+
+impl fmt::Display for RugSignal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RugSignal::CreatorSell => write!(f, "creator sold"),
+            RugSignal::LinkedWalletSell(wallet) => write!(f, "linked wallet {} sold", wallet),
+            RugSignal::LargeSell {
+                sol_amount,
+                real_sol_reserves,
+            } => write!(f, "one sell took {} of {} lamports in the curve", sol_amount, real_sol_reserves),
+            RugSignal::ReserveDrop { peak, now } => {
+                write!(f, "reserves fell from {} to {} lamports", peak, now)
+            }
+        }
+    }
+}
+
+struct WatchedMint {
+    creator: Pubkey,
+    linked_wallets: HashSet<Pubkey>,
+    // (timestamp, virtual_sol_reserves) of recent trades
+    history: VecDeque<(i64, u64)>,
+    triggered: bool,
+}
+
+/// Watches TradeEvents for held mints and fires an exit signal when the
+/// creator (or a wallet they funded) dumps, or the curve drains too fast.
+pub struct RugWatch {
+    config: RugConfig,
+    mints: Mutex<HashMap<Pubkey, WatchedMint>>,
+}
+
+impl RugWatch {
+    pub fn new(config: RugConfig) -> Self {
+        RugWatch {
+            config,
+            mints: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &RugConfig {
+        &self.config
+    }
+
+    /// Starts watching `mint`; watching an already watched mint is a no-op.
+    pub fn watch(&self, mint: Pubkey, creator: Pubkey) {
+        let mut mints = self.mints.lock().unwrap();
+        mints.entry(mint).or_insert_with(|| WatchedMint {
+            creator,
+            linked_wallets: HashSet::new(),
+            history: VecDeque::new(),
+            triggered: false,
+        });
+    }
+
+    pub fn add_linked_wallets(&self, mint: &Pubkey, wallets: Vec<Pubkey>) {
+        if let Some(watched) = self.mints.lock().unwrap().get_mut(mint) {
+            watched.linked_wallets.extend(wallets);
+        }
+    }
+
+    pub fn unwatch(&self, mint: &Pubkey) {
+        self.mints.lock().unwrap().remove(mint);
+    }
+
+    /// Returns the signal the first time a watched mint looks rugged, `None` otherwise.
+    pub fn on_trade(&self, trade_event: &TradeEvent) -> Option<RugSignal> {
+        let mut mints = self.mints.lock().unwrap();
+        let watched = mints.get_mut(&trade_event.mint)?;
+
+        let now = trade_event.get_virtual_sol_reserves();
+        watched.history.push_back((trade_event.get_timestamp(), now));
+        while let Some((ts, _)) = watched.history.front() {
+            if trade_event.get_timestamp() - ts > self.config.drop_window_secs {
+                watched.history.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if watched.triggered {
+            return None;
+        }
+
+        let signal = if trade_event.is_buy_bool() {
+            None
+        } else if trade_event.user == watched.creator {
+            Some(RugSignal::CreatorSell)
+        } else if watched.linked_wallets.contains(&trade_event.user) {
+            Some(RugSignal::LinkedWalletSell(trade_event.user))
+        } else {
+            // reserves before this sell = reserves after + sol paid out
+            let real_sol_reserves =
+                trade_event.get_real_sol_reserves() + trade_event.get_sol_amount();
+            let peak = watched.history.iter().map(|(_, v)| *v).max().unwrap_or(now);
+
+            if real_sol_reserves > 0
+                && trade_event.get_sol_amount() as f64
+                    > real_sol_reserves as f64 * self.config.max_sell_pct
+            {
+                Some(RugSignal::LargeSell {
+                    sol_amount: trade_event.get_sol_amount(),
+                    real_sol_reserves,
+                })
+            } else if (peak - now) as f64 > peak as f64 * self.config.drop_pct {
+                Some(RugSignal::ReserveDrop { peak, now })
+            } else {
+                None
+            }
+        };
+
+        if let Some(signal) = &signal {
+            println!(
+                "{}:: rug signal on {}: {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                trade_event.mint,
+                signal
+            );
+            watched.triggered = true;
+        }
+
+        signal
+    }
+}
+
+/// Plain SOL transfers, as (from, to), in `address`'s last `limit` transactions.
+async fn sol_transfers(client: &RpcClient, address: &Pubkey, limit: usize) -> Vec<(Pubkey, Pubkey)> {
+    let mut transfers = Vec::new();
+
+    let sig_config = GetConfirmedSignaturesForAddress2Config {
+        limit: Some(limit),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..Default::default()
+    };
+    let signatures = match client
+        .get_signatures_for_address_with_config(address, sig_config)
+        .await
+    {
+        Ok(signatures) => signatures,
+        Err(e) => {
+            eprintln!("Failed to fetch signatures for {}: {:?}", address, e);
+            return transfers;
+        }
+    };
+
+    let tx_config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for status in signatures {
+        let signature = match Signature::from_str(&status.signature) {
+            Ok(signature) => signature,
+            Err(_) => continue,
+        };
+        let tx = match client.get_transaction_with_config(&signature, tx_config).await {
+            Ok(tx) => tx,
+            Err(_) => continue,
+        };
+        let versioned = match tx.transaction.transaction.decode() {
+            Some(versioned) => versioned,
+            None => continue,
+        };
+
+        let keys = versioned.message.static_account_keys();
+        for ix in versioned.message.instructions() {
+            if keys.get(ix.program_id_index as usize) != Some(&system_program::id()) {
+                continue;
+            }
+            if let Ok(SystemInstruction::Transfer { .. }) = bincode::deserialize(&ix.data) {
+                let from = ix.accounts.first().and_then(|i| keys.get(*i as usize));
+                let to = ix.accounts.get(1).and_then(|i| keys.get(*i as usize));
+                if let (Some(from), Some(to)) = (from, to) {
+                    transfers.push((*from, *to));
+                }
+            }
+        }
+    }
+
+    transfers
+}
+
+/// Scans the creator's recent transactions for plain SOL transfers out of
+/// their wallet and returns the recipients.
+pub async fn find_creator_funded_wallets(
+    client: &RpcClient,
+    creator: &Pubkey,
+    limit: usize,
+) -> Vec<Pubkey> {
+    let mut funded = Vec::new();
+    for (from, to) in sol_transfers(client, creator, limit).await {
+        if from == *creator && !funded.contains(&to) {
+            funded.push(to);
+        }
+    }
+    funded
+}
+
+/// Wallets that sent SOL to `wallet` within its last `limit` transactions.
+pub async fn find_funders(client: &RpcClient, wallet: &Pubkey, limit: usize) -> Vec<Pubkey> {
+    let mut funders = Vec::new();
+    for (from, to) in sol_transfers(client, wallet, limit).await {
+        if to == *wallet && from != *wallet && !funders.contains(&from) {
+            funders.push(from);
+        }
+    }
+    funders
+}
@@ -0,0 +1,428 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::{timeout, Duration};
+
+use super::bus::Event;
+use super::check_logs_buy_sell::TradeContext;
+use super::layouts::BondingCurveLayoutPF;
+use super::positions::{MintState, Position};
+use crate::strategy::{Fill, Side};
+
+/// What happens to open positions on SIGINT/SIGTERM.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShutdownMode {
+    /// Sell everything on the emergency fee ladder.
+    Flatten,
+    /// Write positions to disk; the next run picks them up.
+    Persist,
+}
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct ShutdownConfig {
+    pub mode: ShutdownMode,
+    /// How long each phase may wait for in-flight sends to resolve.
+    pub wait: Duration,
+    pub positions_file: PathBuf,
+}
+
+impl ShutdownConfig {
+    pub fn from_env() -> Self {
+        let mode = match env::var("shutdown_mode").as_deref() {
+            Ok("flatten") | Err(_) => ShutdownMode::Flatten,
+            Ok("persist") => ShutdownMode::Persist,
+            Ok(other) => panic!("shutdown_mode must be flatten or persist, got {}", other),
+        };
+
+        let wait_secs = env::var("shutdown_wait_secs")
+            .map(|v| v.parse::<u64>().expect("shutdown_wait_secs must be a valid u64"))
+            .unwrap_or(60);
+
+        ShutdownConfig {
+            mode,
+            wait: Duration::from_secs(wait_secs),
+            positions_file: PathBuf::from(env::var("positions_file").unwrap_or("positions.json".to_string())),
+        }
+    }
+}
+
+/// Resolves on the first SIGINT or SIGTERM, with the signal's name.
+pub async fn signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Stops new entries, lets in-flight sends resolve, then flattens or
+/// persists what is still open. Ingestion and strategy timers must already
+/// be stopped.
+pub async fn run(ctx: &Arc<TradeContext>, config: &ShutdownConfig) {
+    let log = |msg: String| println!("{}:: {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg);
+
+    ctx.risk.stop_entries();
+    log("no new entries, waiting for in-flight sends".to_string());
+    if timeout(config.wait, ctx.bus.settled()).await.is_err() {
+        log(format!("sends still in flight after {:?}, moving on", config.wait));
+    }
+
+    let open = ctx.positions.all();
+    match config.mode {
+        _ if open.is_empty() => log("no open positions".to_string()),
+        ShutdownMode::Flatten => {
+            log(format!("selling {} open positions", open.len()));
+            for position in &open {
+                ctx.bus.publish(Event::EmergencySell {
+                    strategy: position.strategy,
+                    mint: position.mint,
+                });
+            }
+            if timeout(config.wait, ctx.bus.settled()).await.is_err() {
+                log(format!("sells still in flight after {:?}", config.wait));
+            }
+            let left = ctx.positions.all();
+            if !left.is_empty() {
+                // better on disk than forgotten
+                match save(ctx, &config.positions_file, &left) {
+                    Ok(()) => log(format!(
+                        "{} positions could not be sold, saved to {}",
+                        left.len(),
+                        config.positions_file.display()
+                    )),
+                    Err(e) => eprintln!("Failed to save positions: {:?}", e),
+                }
+            }
+        }
+        ShutdownMode::Persist => match save(ctx, &config.positions_file, &open) {
+            Ok(()) => log(format!(
+                "saved {} open positions to {}",
+                open.len(),
+                config.positions_file.display()
+            )),
+            Err(e) => eprintln!("Failed to save positions: {:?}", e),
+        },
+    }
+
+    // the journal stage writes each event as it is handled, so it is complete once the bus settles
+    if timeout(config.wait, ctx.bus.settled()).await.is_err() {
+        log("journal may be missing events still in flight".to_string());
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPosition {
+    strategy: usize,
+    wallet: usize,
+    mint: String,
+    bonding_curve: String,
+    creator: String,
+    tokens: u64,
+    cost_lamports: u64,
+    opened_ms: i64,
+    // the curve as last seen, 0 in files from before they were saved
+    #[serde(default)]
+    virtual_sol_reserves: u64,
+    #[serde(default)]
+    virtual_token_reserves: u64,
+}
+
+/// Saves whatever is still open without selling, for a forced exit.
+pub fn save_open(ctx: &Arc<TradeContext>, config: &ShutdownConfig) {
+    let open = ctx.positions.all();
+    if open.is_empty() {
+        return;
+    }
+    match save(ctx, &config.positions_file, &open) {
+        Ok(()) => println!(
+            "{}:: saved {} open positions to {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            open.len(),
+            config.positions_file.display()
+        ),
+        Err(e) => eprintln!("Failed to save positions: {:?}", e),
+    }
+}
+
+fn save(ctx: &Arc<TradeContext>, path: &PathBuf, positions: &[Position]) -> std::io::Result<()> {
+    // what `restore` could not take over stays in the file for a run that can;
+    // an unreadable file must not keep the open positions from being saved
+    let kept = read(path)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to read positions from {}: {:?}", path.display(), e);
+            Vec::new()
+        })
+        .into_iter()
+        .filter(|saved| !restorable(ctx, saved));
+    let saved: Vec<SavedPosition> = positions
+        .iter()
+        .map(|p| {
+            let curve = ctx.positions.mint_state(&p.mint);
+            SavedPosition {
+                strategy: p.strategy,
+                wallet: p.wallet,
+                mint: p.mint.to_string(),
+                bonding_curve: p.bonding_curve.to_string(),
+                creator: p.creator.to_string(),
+                tokens: p.tokens,
+                cost_lamports: p.cost_lamports,
+                opened_ms: p.opened_ms,
+                virtual_sol_reserves: curve.as_ref().map_or(0, |c| c.virtual_sol_reserves),
+                virtual_token_reserves: curve.as_ref().map_or(0, |c| c.virtual_token_reserves),
+            }
+        })
+        .chain(kept)
+        .collect();
+    write(path, &saved)
+}
+
+fn write(path: &PathBuf, saved: &[SavedPosition]) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(saved).map_err(std::io::Error::other)?;
+    // write then rename, so a crash never leaves half a file behind
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, path)
+}
+
+// the positions saved in `path`, empty if there is no file
+fn read(path: &PathBuf) -> std::io::Result<Vec<SavedPosition>> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&json).map_err(std::io::Error::other)
+}
+
+// strategies and wallets are addressed by index, the config may have changed since
+fn restorable(ctx: &Arc<TradeContext>, saved: &SavedPosition) -> bool {
+    saved.strategy < ctx.strategies.len() && saved.wallet < ctx.wallets.len()
+}
+
+/// Mints of the positions saved in `path`, empty if there is no file.
+pub fn saved_mints(path: &PathBuf) -> std::io::Result<HashSet<Pubkey>> {
+    read(path)?
+        .iter()
+        .map(|p| Pubkey::from_str(&p.mint).map_err(std::io::Error::other))
+        .collect()
+}
+
+/// Takes over positions a previous run persisted: books them with their
+/// curve, watches them for rugs and tells their strategies, which then manage
+/// the exit as for any fill. Positions that can't be restored under the
+/// current config are written back, the file is removed once none are left.
+/// Returns how many were restored.
+pub async fn restore(ctx: &Arc<TradeContext>, path: &PathBuf) -> usize {
+    let saved = match read(path) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("Failed to read positions from {}: {:?}", path.display(), e);
+            return 0;
+        }
+    };
+    if saved.is_empty() {
+        return 0;
+    }
+
+    let mut restored = 0;
+    let mut skipped = Vec::new();
+    for saved in saved {
+        let keys = (
+            Pubkey::from_str(&saved.mint),
+            Pubkey::from_str(&saved.bonding_curve),
+            Pubkey::from_str(&saved.creator),
+        );
+        let (mint, bonding_curve, creator) = match keys {
+            (Ok(mint), Ok(bonding_curve), Ok(creator)) => (mint, bonding_curve, creator),
+            _ => {
+                eprintln!("Dropping saved position with a malformed key: {}", saved.mint);
+                continue;
+            }
+        };
+        if !restorable(ctx, &saved) {
+            eprintln!(
+                "Skipping saved position in {}: strategy {} or wallet {} no longer exists",
+                mint, saved.strategy, saved.wallet
+            );
+            skipped.push(saved);
+            continue;
+        }
+
+        // sells are priced from the curve, the chain's is newer than the saved one
+        let reserves = match curve_reserves(&ctx.client, &bonding_curve).await {
+            Some(reserves) => Some(reserves),
+            None if saved.virtual_token_reserves > 0 => {
+                Some((saved.virtual_sol_reserves, saved.virtual_token_reserves))
+            }
+            None => None,
+        };
+        match reserves {
+            Some((virtual_sol_reserves, virtual_token_reserves)) => ctx.positions.track_mint(
+                mint,
+                MintState {
+                    bonding_curve,
+                    creator,
+                    virtual_sol_reserves,
+                    virtual_token_reserves,
+                    create_slot: 0,
+                },
+            ),
+            None => eprintln!("No curve known for saved position in {}, its sell will not be priced", mint),
+        }
+
+        ctx.positions.add(Position {
+            strategy: saved.strategy,
+            wallet: saved.wallet,
+            mint,
+            bonding_curve,
+            creator,
+            tokens: saved.tokens,
+            cost_lamports: saved.cost_lamports,
+            opened_ms: saved.opened_ms,
+        });
+        ctx.strategies.restore(saved.strategy, saved.cost_lamports);
+        ctx.rug_watch.watch(mint, creator);
+
+        // the hold starts over from now
+        let fill = Fill {
+            mint,
+            side: Side::Buy,
+            token_amount: saved.tokens,
+            lamports: saved.cost_lamports,
+            time_ms: ctx.executor.now_ms(),
+            slot: ctx.slots.slot(),
+        };
+        for (strategy, intent) in ctx.strategies.on_fill(saved.strategy, &fill) {
+            ctx.bus.publish(Event::Intent { strategy, intent });
+        }
+        restored += 1;
+    }
+
+    if skipped.is_empty() {
+        if let Err(e) = fs::remove_file(path) {
+            eprintln!("Failed to remove {}: {:?}", path.display(), e);
+        }
+    } else if let Err(e) = write(path, &skipped) {
+        eprintln!("Failed to keep {} skipped positions in {}: {:?}", skipped.len(), path.display(), e);
+    }
+    restored
+}
+
+// (virtual SOL, virtual tokens) of a bonding curve, `None` if it can't be read
+async fn curve_reserves(client: &RpcClient, bonding_curve: &Pubkey) -> Option<(u64, u64)> {
+    let data = client.get_account_data(bonding_curve).await.ok()?;
+    let curve = BondingCurveLayoutPF::decode_pump_fun_bonding_curve_info(data.get(8..)?);
+    Some((curve.get_virtual_sol_reserves(), curve.get_virtual_token_reserves()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::{sim_context, snipe_and_hold};
+
+    fn position(mint: Pubkey) -> Position {
+        Position {
+            strategy: 0,
+            wallet: 0,
+            mint,
+            bonding_curve: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            tokens: 1_000,
+            cost_lamports: 1_000_000,
+            opened_ms: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn restored_positions_keep_their_curve() {
+        let path = env::temp_dir().join(format!("positions-{}.json", Pubkey::new_unique()));
+        let mint = Pubkey::new_unique();
+        let saving = sim_context(snipe_and_hold()).await;
+        let held = position(mint);
+        saving.positions.track_mint(
+            mint,
+            MintState {
+                bonding_curve: held.bonding_curve,
+                creator: held.creator,
+                virtual_sol_reserves: 40_000_000_000,
+                virtual_token_reserves: 800_000_000_000_000,
+                create_slot: 0,
+            },
+        );
+        save(&saving, &path, &[held]).unwrap();
+
+        // the sim context's RPC is unreachable, so the saved reserves are used
+        let ctx = sim_context(snipe_and_hold()).await;
+        assert_eq!(restore(&ctx, &path).await, 1);
+        let state = ctx.positions.mint_state(&mint).unwrap();
+        assert_eq!(state.virtual_sol_reserves, 40_000_000_000);
+        assert_eq!(state.virtual_token_reserves, 800_000_000_000_000);
+        assert_eq!(ctx.positions.holders_of(&mint), vec![0]);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn files_without_reserves_still_restore() {
+        let path = env::temp_dir().join(format!("positions-{}.json", Pubkey::new_unique()));
+        let mint = Pubkey::new_unique();
+        let held = position(mint);
+        let json = serde_json::json!([{
+            "strategy": 0,
+            "wallet": 0,
+            "mint": mint.to_string(),
+            "bonding_curve": held.bonding_curve.to_string(),
+            "creator": held.creator.to_string(),
+            "tokens": held.tokens,
+            "cost_lamports": held.cost_lamports,
+            "opened_ms": 0,
+        }]);
+        fs::write(&path, json.to_string()).unwrap();
+
+        let ctx = sim_context(snipe_and_hold()).await;
+        assert_eq!(restore(&ctx, &path).await, 1);
+        assert_eq!(ctx.positions.holders_of(&mint), vec![0]);
+        assert!(ctx.positions.mint_state(&mint).is_none());
+    }
+
+    #[tokio::test]
+    async fn positions_of_a_removed_wallet_stay_saved() {
+        let path = env::temp_dir().join(format!("positions-{}.json", Pubkey::new_unique()));
+        let (kept, gone) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let saving = sim_context(snipe_and_hold()).await;
+        let in_second_wallet = Position {
+            wallet: 1,
+            ..position(gone)
+        };
+        save(&saving, &path, &[position(kept), in_second_wallet]).unwrap();
+
+        // the sim context has a single wallet
+        let ctx = sim_context(snipe_and_hold()).await;
+        assert_eq!(restore(&ctx, &path).await, 1);
+        assert_eq!(ctx.positions.holders_of(&kept), vec![0]);
+        assert!(ctx.positions.holders_of(&gone).is_empty());
+        assert_eq!(saved_mints(&path).unwrap(), HashSet::from([gone]));
+
+        // shutting down again saves what is open next to what was kept
+        save(&ctx, &path, &ctx.positions.all()).unwrap();
+        assert_eq!(saved_mints(&path).unwrap(), HashSet::from([kept, gone]));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+use futures_util::stream::StreamExt;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message::Text;
+
+use crate::sol_wss_methods::slot_subscribe::send_slot_subscribe;
+
+/// Newest slot seen, from `slotSubscribe` and from the context of every
+/// notification. During replay only the recorded notifications feed it.
+pub struct SlotClock {
+    slot: AtomicU64,
+}
+
+impl SlotClock {
+    pub fn new() -> Self {
+        SlotClock {
+            slot: AtomicU64::new(0),
+        }
+    }
+
+    /// Follows `slotSubscribe` in the background, reconnecting on errors.
+    pub fn spawn(wss_url: String) -> Arc<Self> {
+        let clock = Arc::new(SlotClock::new());
+        let follower = clock.clone();
+        tokio::spawn(async move {
+            follower.follow(wss_url).await;
+        });
+        clock
+    }
+
+    async fn follow(&self, wss_url: String) {
+        loop {
+            match connect_async(wss_url.to_string()).await {
+                Ok((mut stream, _)) => {
+                    if let Err(e) = send_slot_subscribe(&mut stream).await {
+                        eprintln!("Failed to subscribe to slots: {:?}", e);
+                    } else {
+                        // pings and other frames are skipped, the stream ends on close
+                        while let Some(message) = stream.next().await {
+                            match message {
+                                Ok(Text(text)) => {
+                                    if let Ok(parsed) = serde_json::from_str::<Value>(&text) {
+                                        if let Some(slot) =
+                                            parsed["params"]["result"]["slot"].as_u64()
+                                        {
+                                            self.observe(slot);
+                                        }
+                                    }
+                                }
+                                Ok(_) => {}
+                                Err(e) => {
+                                    eprintln!("Slot WebSocket message error: {:?}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Slot WebSocket connection error: {:?}", e),
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Slots only move forward; older observations are ignored.
+    pub fn observe(&self, slot: u64) {
+        self.slot.fetch_max(slot, Ordering::SeqCst);
+    }
+
+    /// 0 until the first slot was seen.
+    pub fn slot(&self) -> u64 {
+        self.slot.load(Ordering::SeqCst)
+    }
+
+    /// Slots since `since`, `None` while either side is unknown.
+    pub fn lag(&self, since: u64) -> Option<u64> {
+        match (self.slot(), since) {
+            (0, _) | (_, 0) => None,
+            (now, since) => Some(now.saturating_sub(since)),
+        }
+    }
+}
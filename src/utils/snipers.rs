@@ -0,0 +1,343 @@
+use chrono::Local;
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use tokio::sync::mpsc;
+
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, compute_budget, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::UiTransactionEncoding;
+
+use super::layouts::TradeEvent;
+use crate::txn::fees::compute_unit_price_data;
+
+// launches whose early buyers we keep, oldest are forgotten first
+const MAX_LAUNCHES: usize = 2_000;
+// wallets on the leaderboard; past this the half with the fewest launches goes
+const MAX_WALLETS: usize = 50_000;
+// early buys waiting for their fee to be read, more are dropped
+const MAX_FEE_LOOKUPS: usize = 256;
+
+/// Loaded from env, see README.
+#[derive(Clone, Debug)]
+pub struct SniperConfig {
+    /// Buys within this many slots of the create count as early; 1 means the create slot only.
+    pub window_slots: u64,
+    /// Where early buys are appended as JSONL, nothing is written when unset.
+    pub flush_dir: Option<PathBuf>,
+}
+
+impl SniperConfig {
+    pub fn from_env() -> Self {
+        let window_slots = env::var("sniper_window_slots")
+            .map(|v| v.parse::<u64>().expect("sniper_window_slots must be a valid u64"))
+            .unwrap_or(3);
+
+        SniperConfig {
+            window_slots: window_slots.max(1),
+            flush_dir: env::var("sniper_dir").ok().map(PathBuf::from),
+        }
+    }
+}
+
+/// One buy into a launch within the early window.
+#[derive(Clone, Debug)]
+pub struct EarlyBuy {
+    pub mint: Pubkey,
+    pub wallet: Pubkey,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    /// Slots after the create; 0 is the create slot itself.
+    pub slot_offset: u64,
+    pub signature: String,
+    /// Compute unit price in micro-lamports, once read from the transaction.
+    pub priority_fee: Option<u64>,
+}
+
+/// A wallet's record across all launches seen.
+#[derive(Clone, Debug, Default)]
+pub struct SniperStats {
+    /// Launches it bought early into.
+    pub launches: usize,
+    /// Of those, launches it bought into within the create slot.
+    pub block0: usize,
+    pub total_sol: u64,
+    fee_sum: u64,
+    fee_count: u64,
+}
+
+impl SniperStats {
+    pub fn avg_priority_fee(&self) -> Option<u64> {
+        (self.fee_count > 0).then(|| self.fee_sum / self.fee_count)
+    }
+}
+
+struct Launch {
+    create_slot: u64,
+    creator: Pubkey,
+    buys: Vec<EarlyBuy>,
+}
+
+#[derive(Default)]
+struct BoardState {
+    launches: HashMap<Pubkey, Launch>,
+    order: VecDeque<Pubkey>,
+    wallets: HashMap<Pubkey, SniperStats>,
+    pending_flush: Vec<EarlyBuy>,
+}
+
+/// Who buys launches in their first slots, per launch and as a running
+/// leaderboard of recurring wallets.
+pub struct SniperBoard {
+    config: SniperConfig,
+    state: Mutex<BoardState>,
+    fee_lookups: OnceLock<mpsc::Sender<(Pubkey, Signature)>>,
+}
+
+impl SniperBoard {
+    pub fn new(config: SniperConfig) -> Self {
+        SniperBoard {
+            config,
+            state: Mutex::new(BoardState::default()),
+            fee_lookups: OnceLock::new(),
+        }
+    }
+
+    /// Reads the priority fee of early buys one at a time on `client`, which
+    /// should not be the one trading. Without this no fees are read.
+    pub fn spawn_fee_lookups(self: &Arc<Self>, client: RpcClient) {
+        let (tx, mut rx) = mpsc::channel::<(Pubkey, Signature)>(MAX_FEE_LOOKUPS);
+        if self.fee_lookups.set(tx).is_err() {
+            return;
+        }
+        let board: Weak<SniperBoard> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            while let Some((mint, signature)) = rx.recv().await {
+                let fee = fetch_priority_fee(&client, &signature).await;
+                match (board.upgrade(), fee) {
+                    (Some(board), Some(fee)) => {
+                        board.set_priority_fee(&mint, &signature.to_string(), fee)
+                    }
+                    (Some(_), None) => {}
+                    (None, _) => break,
+                }
+            }
+        });
+    }
+
+    /// Queues reading the fee of an early buy, dropped when the queue is full.
+    pub fn request_priority_fee(&self, mint: Pubkey, signature: Signature) {
+        if let Some(tx) = self.fee_lookups.get() {
+            let _ = tx.try_send((mint, signature));
+        }
+    }
+
+    pub fn on_create(&self, mint: Pubkey, creator: Pubkey, slot: u64) {
+        if slot == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.launches.contains_key(&mint) {
+            return;
+        }
+        state.launches.insert(
+            mint,
+            Launch {
+                create_slot: slot,
+                creator,
+                buys: Vec::new(),
+            },
+        );
+        state.order.push_back(mint);
+        while state.order.len() > MAX_LAUNCHES {
+            if let Some(old) = state.order.pop_front() {
+                state.launches.remove(&old);
+            }
+        }
+    }
+
+    /// Records `trade` if it is a buy inside its launch's early window, the
+    /// creator's own buys aside. Returns whether it was recorded.
+    pub fn on_trade(&self, trade: &TradeEvent, slot: u64, signature: &str) -> bool {
+        if !trade.is_buy_bool() {
+            return false;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let launch = match state.launches.get_mut(&trade.mint) {
+            Some(launch) => launch,
+            None => return false,
+        };
+        let slot_offset = match slot.checked_sub(launch.create_slot) {
+            Some(offset) if offset < self.config.window_slots => offset,
+            _ => return false,
+        };
+        if trade.user == launch.creator {
+            return false;
+        }
+
+        // a wallet counts once per launch, at its earliest buy
+        let first_buy = !launch.buys.iter().any(|b| b.wallet == trade.user);
+        let stats = state.wallets.entry(trade.user).or_default();
+        if first_buy {
+            stats.launches += 1;
+            if slot_offset == 0 {
+                stats.block0 += 1;
+            }
+        }
+        stats.total_sol += trade.get_sol_amount();
+        if state.wallets.len() > MAX_WALLETS {
+            prune_wallets(&mut state.wallets);
+        }
+
+        let buy = EarlyBuy {
+            mint: trade.mint,
+            wallet: trade.user,
+            sol_amount: trade.get_sol_amount(),
+            token_amount: trade.token_amount,
+            slot_offset,
+            signature: signature.to_string(),
+            priority_fee: None,
+        };
+        launch.buys.push(buy.clone());
+        if self.config.flush_dir.is_some() {
+            state.pending_flush.push(buy);
+        }
+        true
+    }
+
+    /// Attaches the compute unit price read from an early buy's transaction.
+    pub fn set_priority_fee(&self, mint: &Pubkey, signature: &str, fee: u64) {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let wallet = match state.launches.get_mut(mint) {
+            Some(launch) => launch.buys.iter_mut().find(|b| b.signature == signature).map(|buy| {
+                buy.priority_fee = Some(fee);
+                buy.wallet
+            }),
+            None => None,
+        };
+        if let Some(stats) = wallet.and_then(|w| state.wallets.get_mut(&w)) {
+            stats.fee_sum += fee;
+            stats.fee_count += 1;
+        }
+        if let Some(buy) = state
+            .pending_flush
+            .iter_mut()
+            .find(|b| b.mint == *mint && b.signature == signature)
+        {
+            buy.priority_fee = Some(fee);
+        }
+    }
+
+    /// Distinct wallets, the creator aside, that bought `mint` in its create slot.
+    pub fn snipers_in_block0(&self, mint: &Pubkey) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .launches
+            .get(mint)
+            .map(|launch| {
+                launch
+                    .buys
+                    .iter()
+                    .filter(|b| b.slot_offset == 0)
+                    .map(|b| b.wallet)
+                    .collect::<HashSet<_>>()
+                    .len()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Wallet and tokens of each create-slot buy of `mint` seen so far.
+    pub fn block0_buys(&self, mint: &Pubkey) -> Vec<(Pubkey, u64)> {
+        let state = self.state.lock().unwrap();
+        state
+            .launches
+            .get(mint)
+            .map(|launch| {
+                launch
+                    .buys
+                    .iter()
+                    .filter(|b| b.slot_offset == 0)
+                    .map(|b| (b.wallet, b.token_amount))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The `count` wallets that bought the most launches early.
+    pub fn leaderboard(&self, count: usize) -> Vec<(Pubkey, SniperStats)> {
+        let state = self.state.lock().unwrap();
+        let mut wallets: Vec<(Pubkey, SniperStats)> =
+            state.wallets.iter().map(|(w, s)| (*w, s.clone())).collect();
+        wallets.sort_by(|a, b| b.1.launches.cmp(&a.1.launches).then(b.1.block0.cmp(&a.1.block0)));
+        wallets.truncate(count);
+        wallets
+    }
+
+    /// Appends recorded early buys to `sniper_dir/snipers-<date>.jsonl`.
+    pub fn flush(&self) -> std::io::Result<usize> {
+        let dir = match &self.config.flush_dir {
+            Some(dir) => dir,
+            None => return Ok(0),
+        };
+
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending_flush);
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("snipers-{}.jsonl", Local::now().format("%Y-%m-%d")));
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        for buy in &pending {
+            let line = json!({
+                "mint": buy.mint.to_string(),
+                "wallet": buy.wallet.to_string(),
+                "sol_amount": buy.sol_amount,
+                "token_amount": buy.token_amount,
+                "slot_offset": buy.slot_offset,
+                "signature": buy.signature,
+                "priority_fee": buy.priority_fee,
+            });
+            writeln!(file, "{}", line)?;
+        }
+
+        Ok(pending.len())
+    }
+}
+
+/// Keeps the half of `wallets` that bought the most launches early.
+fn prune_wallets(wallets: &mut HashMap<Pubkey, SniperStats>) {
+    let mut ranked: Vec<(usize, Pubkey)> = wallets.iter().map(|(w, s)| (s.launches, *w)).collect();
+    ranked.select_nth_unstable_by(MAX_WALLETS / 2, |a, b| b.0.cmp(&a.0));
+    for (_, wallet) in &ranked[MAX_WALLETS / 2..] {
+        wallets.remove(wallet);
+    }
+}
+
+/// Compute unit price, in micro-lamports, that a transaction set. `None`
+/// if it set none or could not be fetched.
+pub async fn fetch_priority_fee(client: &RpcClient, signature: &Signature) -> Option<u64> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = client.get_transaction_with_config(signature, config).await.ok()?;
+    let tx = tx.transaction.transaction.decode()?;
+
+    let keys = tx.message.static_account_keys();
+    tx.message.instructions().iter().find_map(|ix| {
+        if keys.get(ix.program_id_index as usize) != Some(&compute_budget::id()) {
+            return None;
+        }
+        compute_unit_price_data(&ix.data)
+    })
+}
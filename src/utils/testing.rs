@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, signature::Keypair};
+
+use super::bus::EventBus;
+use super::candles::{CandleConfig, CandleStore};
+use super::check_logs_buy_sell::TradeContext;
+use super::holders::{HolderAnalyser, HolderConfig};
+use super::insiders::{InsiderConfig, InsiderDetector};
+use super::metadata::{MetadataConfig, MetadataFilter};
+use super::positions::PositionBook;
+use super::risk::{RiskConfig, RiskManager};
+use super::rug_watch::{RugConfig, RugWatch};
+use super::slots::SlotClock;
+use super::snipers::{SniperBoard, SniperConfig};
+use crate::strategy::runner::{EntryLimits, StrategyRunner};
+use crate::strategy::snipe_and_hold::SnipeAndHold;
+use crate::strategy::Strategy;
+use crate::txn::executor::{Executor, SimExecutor};
+use crate::txn::fees::FeeLadders;
+use crate::txn::spam_txn::array_of_fees;
+use crate::wallet::pool::{RotationPolicy, WalletPool};
+
+/// `snipe_and_hold` alone as strategy 0: 0.001 SOL per buy, a 10s hold, no
+/// early exits and no budget limit.
+pub fn snipe_and_hold() -> StrategyRunner {
+    let strategy: Box<dyn Strategy> =
+        Box::new(SnipeAndHold::new("snipe_and_hold#0".to_string(), 1_000_000, 10_000, None, None));
+    StrategyRunner::new(vec![(strategy, u64::MAX, EntryLimits::default())])
+}
+
+/// A replay context: simulated executor, one throwaway wallet and an RPC
+/// client pointed at nothing, so any lookup fails fast.
+pub async fn sim_context(strategies: StrategyRunner) -> Arc<TradeContext> {
+    let client = Arc::new(RpcClient::new("http://127.0.0.1:1".to_string()));
+    let fees = FeeLadders::new(
+        200_000,
+        array_of_fees(3, 1_000).await,
+        array_of_fees(3, 10_000).await,
+        None,
+        None,
+    );
+
+    Arc::new(TradeContext {
+        client,
+        wallets: WalletPool::new(vec![Arc::new(Keypair::new())], RotationPolicy::RoundRobin),
+        slippage: 0.0,
+        unit_limit_ix: ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+        fees,
+        lookup_table: None,
+        nonces: None,
+        rug_watch: Arc::new(RugWatch::new(RugConfig::from_env())),
+        candle_store: Arc::new(CandleStore::new(CandleConfig::from_env())),
+        strategies,
+        positions: PositionBook::new(),
+        risk: RiskManager::new(RiskConfig::from_env()),
+        executor: Executor::Simulated(SimExecutor::new()),
+        slots: Arc::new(SlotClock::new()),
+        snipers: Arc::new(SniperBoard::new(SniperConfig::from_env())),
+        insiders: Arc::new(InsiderDetector::new(InsiderConfig::from_env())),
+        metadata: Arc::new(MetadataFilter::new(MetadataConfig::from_env())),
+        holders: Arc::new(HolderAnalyser::new(HolderConfig::from_env())),
+        bus: EventBus::new(1_024),
+    })
+}
@@ -0,0 +1,87 @@
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{UiTransactionEncoding, UiTransactionTokenBalance};
+use tokio::time::{sleep, Duration};
+
+// how long to wait for a sent transaction to confirm
+const CONFIRM_ATTEMPTS: u32 = 20;
+const CONFIRM_DELAY: Duration = Duration::from_millis(500);
+
+fn owned_amount(balances: Option<Vec<UiTransactionTokenBalance>>, owner: &str, mint: &str) -> u64 {
+    balances
+        .unwrap_or_default()
+        .iter()
+        .filter(|b| b.mint == mint && Option::<&String>::from(b.owner.as_ref()).map(|o| o.as_str()) == Some(owner))
+        .filter_map(|b| b.ui_token_amount.amount.parse::<u64>().ok())
+        .sum()
+}
+
+/// A confirmed transaction's effect on one token balance.
+#[derive(Clone, Copy, Debug)]
+pub struct TokenDelta {
+    pub tokens: u64,
+    /// Tokens of `mint` the owner gave up, what a sell actually sold.
+    pub sold: u64,
+    /// Slot the transaction was included in.
+    pub slot: u64,
+}
+
+/// Tokens of `mint` that `owner` gained or gave up in the transaction, read
+/// from its pre/post token balances once confirmed. Zero both ways when the
+/// transaction failed, `None` when it could not be fetched in time.
+pub async fn token_delta(client: &RpcClient, signature: &Signature, owner: &Pubkey, mint: &Pubkey) -> Option<TokenDelta> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for _ in 0..CONFIRM_ATTEMPTS {
+        let tx = match client.get_transaction_with_config(signature, config).await {
+            Ok(tx) => tx,
+            Err(_) => {
+                sleep(CONFIRM_DELAY).await;
+                continue;
+            }
+        };
+
+        let slot = tx.slot;
+        let meta = tx.transaction.meta?;
+        if meta.err.is_some() {
+            return Some(TokenDelta {
+                tokens: 0,
+                sold: 0,
+                slot,
+            });
+        }
+
+        let owner = owner.to_string();
+        let mint = mint.to_string();
+        let pre = owned_amount(meta.pre_token_balances.into(), &owner, &mint);
+        let post = owned_amount(meta.post_token_balances.into(), &owner, &mint);
+        return Some(TokenDelta {
+            tokens: post.saturating_sub(pre),
+            sold: pre.saturating_sub(post),
+            slot,
+        });
+    }
+
+    None
+}
+
+/// Raw token amount held by `ata`, `None` if it does not exist or the RPC failed.
+pub async fn ata_balance(client: &RpcClient, ata: &Pubkey) -> Option<u64> {
+    client
+        .get_token_account_balance_with_commitment(ata, CommitmentConfig::confirmed())
+        .await
+        .ok()
+        .and_then(|balance| balance.value.amount.parse::<u64>().ok())
+}
+
+/// `true` once `ata` no longer exists, `false` while it does or if the RPC failed.
+pub async fn ata_closed(client: &RpcClient, ata: &Pubkey) -> bool {
+    matches!(
+        client.get_account_with_commitment(ata, CommitmentConfig::confirmed()).await,
+        Ok(response) if response.value.is_none()
+    )
+}
@@ -0,0 +1,251 @@
+use chrono::Local;
+use futures_util::stream::StreamExt;
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message::Text;
+
+use crate::sol_wss_methods::account_subscribe::send_account_subscribe;
+use crate::txn::fees::compute_unit_price;
+use crate::utils::constants::{ACCOUNT_LEN, LAMPORTS_PER_SOL};
+
+// base fee per signature
+const BASE_FEE_LAMPORTS: u64 = 5_000;
+
+#[derive(Clone, Debug)]
+pub struct BalanceConfig {
+    /// Lamports that must stay in the wallet after a buy's worst-case cost.
+    pub min_wallet_reserve_lamports: u64,
+    /// Buys that would have to be resized below this are refused instead.
+    pub min_trade_lamports: u64,
+}
+
+impl BalanceConfig {
+    pub fn from_env() -> Self {
+        let min_wallet_reserve = env::var("min_wallet_reserve")
+            .map(|v| v.parse::<f64>().expect("min_wallet_reserve must be a valid f64"))
+            .unwrap_or(0.05);
+
+        let min_trade_size = env::var("min_trade_size")
+            .map(|v| v.parse::<f64>().expect("min_trade_size must be a valid f64"))
+            .unwrap_or(0.005);
+
+        BalanceConfig {
+            min_wallet_reserve_lamports: (min_wallet_reserve * LAMPORTS_PER_SOL as f64) as u64,
+            min_trade_lamports: (min_trade_size * LAMPORTS_PER_SOL as f64) as u64,
+        }
+    }
+}
+
+/// Everything a buy can take out of the wallet.
+#[derive(Clone, Debug)]
+pub struct TradeCost {
+    /// `max_sol_cost` of the buy instruction.
+    pub max_sol_cost: u64,
+    pub ata_rent: u64,
+    /// Highest tier of the fee ladder plus the base fee.
+    pub worst_case_fee: u64,
+}
+
+impl TradeCost {
+    pub fn total(&self) -> u64 {
+        self.max_sol_cost + self.ata_rent + self.worst_case_fee
+    }
+
+    fn fixed(&self) -> u64 {
+        self.ata_rent + self.worst_case_fee
+    }
+}
+
+#[derive(Debug)]
+pub enum BalanceRejection {
+    Unknown,
+    Insufficient { available: u64, needed: u64 },
+}
+
+impl fmt::Display for BalanceRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceRejection::Unknown => write!(f, "balance not known yet"),
+            BalanceRejection::Insufficient { available, needed } => {
+                write!(f, "{} lamports spendable, {} needed", available, needed)
+            }
+        }
+    }
+}
+
+/// Caches the payer's lamports, kept fresh by `accountSubscribe`, and keeps
+/// track of what in-flight buys may still spend.
+pub struct BalanceService {
+    config: BalanceConfig,
+    lamports: AtomicU64,
+    known: AtomicBool,
+    reserved: AtomicU64,
+    ata_rent: u64,
+}
+
+impl BalanceService {
+    /// Fetches the starting balance and token account rent, then follows the
+    /// payer's account over `wss_url`.
+    pub async fn spawn(
+        config: BalanceConfig,
+        client: Arc<RpcClient>,
+        wss_url: String,
+        owner: Pubkey,
+    ) -> Arc<Self> {
+        let ata_rent = client
+            .get_minimum_balance_for_rent_exemption(ACCOUNT_LEN)
+            .await
+            .unwrap_or(2_039_280);
+
+        let service = Arc::new(BalanceService {
+            config,
+            lamports: AtomicU64::new(0),
+            known: AtomicBool::new(false),
+            reserved: AtomicU64::new(0),
+            ata_rent,
+        });
+
+        match client.get_balance(&owner).await {
+            Ok(lamports) => service.set_lamports(lamports),
+            Err(e) => eprintln!("Failed to fetch wallet balance: {:?}", e),
+        }
+
+        let follower = service.clone();
+        tokio::spawn(async move {
+            follower.follow(wss_url, owner).await;
+        });
+
+        service
+    }
+
+    async fn follow(&self, wss_url: String, owner: Pubkey) {
+        loop {
+            match connect_async(wss_url.to_string()).await {
+                Ok((mut stream, _)) => {
+                    if let Err(e) = send_account_subscribe(&mut stream, &owner).await {
+                        eprintln!("Failed to subscribe to wallet: {:?}", e);
+                    } else {
+                        // pings and other frames are skipped, the stream ends on close
+                        while let Some(message) = stream.next().await {
+                            match message {
+                                Ok(Text(text)) => {
+                                    if let Ok(parsed) = serde_json::from_str::<Value>(&text) {
+                                        if let Some(lamports) = parsed["params"]["result"]
+                                            ["value"]["lamports"]
+                                            .as_u64()
+                                        {
+                                            self.set_lamports(lamports);
+                                        }
+                                    }
+                                }
+                                Ok(_) => {}
+                                Err(e) => {
+                                    eprintln!("Wallet WebSocket message error: {:?}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Wallet WebSocket connection error: {:?}", e),
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+    }
+
+    pub fn set_lamports(&self, lamports: u64) {
+        self.lamports.store(lamports, Ordering::SeqCst);
+        self.known.store(true, Ordering::SeqCst);
+    }
+
+    pub fn lamports(&self) -> Option<u64> {
+        if self.known.load(Ordering::SeqCst) {
+            Some(self.lamports.load(Ordering::SeqCst))
+        } else {
+            None
+        }
+    }
+
+    /// Balance minus what in-flight buys may still spend.
+    pub fn available(&self) -> Option<u64> {
+        self.lamports()
+            .map(|lamports| lamports.saturating_sub(self.reserved.load(Ordering::SeqCst)))
+    }
+
+    pub fn trade_cost(
+        &self,
+        max_sol_cost: u64,
+        unit_limit_ix: &Instruction,
+        prices_4_spam: &[Instruction],
+    ) -> TradeCost {
+        let unit_limit = compute_unit_limit(unit_limit_ix).unwrap_or(200_000) as u64;
+        let max_price = prices_4_spam
+            .iter()
+            .filter_map(compute_unit_price)
+            .max()
+            .unwrap_or(0);
+
+        TradeCost {
+            max_sol_cost,
+            ata_rent: self.ata_rent,
+            worst_case_fee: BASE_FEE_LAMPORTS
+                + (max_price as u128 * unit_limit as u128 / 1_000_000) as u64,
+        }
+    }
+
+    /// Fits a buy of `lamports` (before the 3% fee headroom) into what the
+    /// wallet can spend while keeping the reserve. Returns the possibly smaller
+    /// size and the amount held back until `release` is called.
+    pub fn fit_buy(&self, lamports: u64, cost: &TradeCost) -> Result<(u64, u64), BalanceRejection> {
+        let available = self.available().ok_or(BalanceRejection::Unknown)?;
+        let spendable = available.saturating_sub(self.config.min_wallet_reserve_lamports);
+
+        let lamports = if cost.total() <= spendable {
+            lamports
+        } else {
+            // max_sol_cost carries 3% headroom over the investment
+            let room = spendable.saturating_sub(cost.fixed());
+            let resized = (room as f64 / 1.03) as u64;
+            if resized < self.config.min_trade_lamports.max(1) {
+                return Err(BalanceRejection::Insufficient {
+                    available: spendable,
+                    needed: cost.total(),
+                });
+            }
+            println!(
+                "{}:: resizing buy from {} to {} lamports to keep the wallet reserve",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                lamports,
+                resized
+            );
+            resized
+        };
+
+        let reserved = (lamports as f64 * 1.03) as u64 + cost.fixed();
+        self.reserved.fetch_add(reserved, Ordering::SeqCst);
+        Ok((lamports, reserved))
+    }
+
+    pub fn release(&self, reserved: u64) {
+        let _ = self
+            .reserved
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |r| Some(r.saturating_sub(reserved)));
+    }
+}
+
+// ComputeBudgetInstruction::SetComputeUnitLimit is tag 2 followed by a u32
+fn compute_unit_limit(ix: &Instruction) -> Option<u32> {
+    match ix.data.split_first() {
+        Some((2, rest)) if rest.len() >= 4 => Some(u32::from_le_bytes(rest[..4].try_into().ok()?)),
+        _ => None,
+    }
+}
+
@@ -0,0 +1,105 @@
+use chrono::Local;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction, transaction::Transaction,
+};
+
+use super::pool::WalletPool;
+use crate::utils::constants::LAMPORTS_PER_SOL;
+
+// fee for a single-signature transfer
+const TRANSFER_FEE_LAMPORTS: u64 = 5_000;
+
+async fn transfer(client: &RpcClient, from: &Keypair, to: &Pubkey, lamports: u64) -> Result<String, String> {
+    let ix = system_instruction::transfer(&from.pubkey(), to, lamports);
+    let recent_blockhash = client.get_latest_blockhash().await.map_err(|e| e.to_string())?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&from.pubkey()), &[from], recent_blockhash);
+    client
+        .send_and_confirm_transaction(&tx)
+        .await
+        .map(|sig| sig.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub async fn list_wallets(client: &RpcClient, treasury: &Keypair, pool: &WalletPool) {
+    let treasury_lamports = client.get_balance(&treasury.pubkey()).await.unwrap_or(0);
+    println!(
+        "treasury {}: {:.4} SOL",
+        treasury.pubkey(),
+        treasury_lamports as f64 / LAMPORTS_PER_SOL as f64
+    );
+    for wallet in pool.wallets() {
+        match client.get_balance(&wallet.pubkey).await {
+            Ok(lamports) => println!(
+                "wallet   {}: {:.4} SOL",
+                wallet.pubkey,
+                lamports as f64 / LAMPORTS_PER_SOL as f64
+            ),
+            Err(e) => eprintln!("Failed to fetch balance of {}: {:?}", wallet.pubkey, e),
+        }
+    }
+}
+
+/// Tops every wallet in the pool up to `target_lamports` from the treasury.
+pub async fn fund_wallets(client: &RpcClient, treasury: &Keypair, pool: &WalletPool, target_lamports: u64) {
+    for wallet in pool.wallets() {
+        if wallet.pubkey == treasury.pubkey() {
+            continue;
+        }
+        let lamports = match client.get_balance(&wallet.pubkey).await {
+            Ok(lamports) => lamports,
+            Err(e) => {
+                eprintln!("Failed to fetch balance of {}: {:?}", wallet.pubkey, e);
+                continue;
+            }
+        };
+        if lamports >= target_lamports {
+            continue;
+        }
+
+        let amount = target_lamports - lamports;
+        match transfer(client, treasury, &wallet.pubkey, amount).await {
+            Ok(sig) => println!(
+                "{}:: funded {} with {} lamports: {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                wallet.pubkey,
+                amount,
+                sig
+            ),
+            Err(e) => eprintln!("Failed to fund {}: {}", wallet.pubkey, e),
+        }
+    }
+}
+
+/// Moves everything above `keep_lamports` from each wallet back to the treasury.
+pub async fn sweep_wallets(client: &RpcClient, treasury: &Keypair, pool: &WalletPool, keep_lamports: u64) {
+    for wallet in pool.wallets() {
+        if wallet.pubkey == treasury.pubkey() {
+            continue;
+        }
+        let lamports = match client.get_balance(&wallet.pubkey).await {
+            Ok(lamports) => lamports,
+            Err(e) => {
+                eprintln!("Failed to fetch balance of {}: {:?}", wallet.pubkey, e);
+                continue;
+            }
+        };
+
+        let amount = lamports.saturating_sub(keep_lamports + TRANSFER_FEE_LAMPORTS);
+        if amount == 0 {
+            continue;
+        }
+
+        match transfer(client, &wallet.keypair, &treasury.pubkey(), amount).await {
+            Ok(sig) => println!(
+                "{}:: swept {} lamports from {}: {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                amount,
+                wallet.pubkey,
+                sig
+            ),
+            Err(e) => eprintln!("Failed to sweep {}: {}", wallet.pubkey, e),
+        }
+    }
+}
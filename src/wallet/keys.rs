@@ -0,0 +1,200 @@
+use aes_gcm_siv::aead::{Aead, NewAead};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use hmac::Hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+const KEYSTORE_VERSION: u32 = 1;
+const KDF_ITERATIONS: u32 = 600_000;
+
+// Password of the last keystore that opened, tried first for the next one
+static CACHED_PASSWORD: Mutex<Option<Zeroizing<String>>> = Mutex::new(None);
+
+// Everything read from KEYSTORE_PASSWORD_FD and not handed out yet, one password per line
+static FD_PASSWORDS: Mutex<Option<Zeroizing<String>>> = Mutex::new(None);
+
+/// Keypair encrypted with AES-256-GCM-SIV under a PBKDF2-SHA256 derived key.
+#[derive(Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub pubkey: String,
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, iterations, key.as_mut());
+    key
+}
+
+pub fn encrypt_keypair(keypair: &Keypair, password: &str) -> Keystore {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, KDF_ITERATIONS);
+    let cipher = Aes256GcmSiv::new(&(*key).into());
+    let secret = Zeroizing::new(keypair.to_bytes());
+    let ciphertext = cipher
+        .encrypt(&Nonce::from(nonce), secret.as_ref())
+        .expect("keypair encryption failed");
+
+    Keystore {
+        version: KEYSTORE_VERSION,
+        pubkey: keypair.pubkey().to_string(),
+        kdf: "pbkdf2-sha256".to_string(),
+        iterations: KDF_ITERATIONS,
+        salt: base64::encode(salt),
+        nonce: base64::encode(nonce),
+        ciphertext: base64::encode(ciphertext),
+    }
+}
+
+pub fn decrypt_keystore(keystore: &Keystore, password: &str) -> Result<Keypair, String> {
+    if keystore.version != KEYSTORE_VERSION || keystore.kdf != "pbkdf2-sha256" {
+        return Err(format!("unsupported keystore v{} ({})", keystore.version, keystore.kdf));
+    }
+    let salt = base64::decode(&keystore.salt).map_err(|e| e.to_string())?;
+    let nonce = base64::decode(&keystore.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = base64::decode(&keystore.ciphertext).map_err(|e| e.to_string())?;
+    let nonce: [u8; 12] = nonce
+        .try_into()
+        .map_err(|_| "keystore nonce must be 12 bytes".to_string())?;
+
+    let key = derive_key(password, &salt, keystore.iterations);
+    let cipher = Aes256GcmSiv::new(&(*key).into());
+    let secret = Zeroizing::new(
+        cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+            .map_err(|_| "wrong password or corrupted keystore".to_string())?,
+    );
+
+    Keypair::from_bytes(&secret).map_err(|e| e.to_string())
+}
+
+/// Reads the keystore password from the file descriptor in `KEYSTORE_PASSWORD_FD`
+/// when set, one line per call, otherwise prompts on the terminal.
+pub fn read_password(prompt: &str) -> Result<Zeroizing<String>, String> {
+    if let Ok(fd) = env::var("KEYSTORE_PASSWORD_FD") {
+        let fd = fd
+            .parse::<i32>()
+            .map_err(|_| "KEYSTORE_PASSWORD_FD must be a valid fd".to_string())?;
+        return read_password_fd(fd);
+    }
+
+    rpassword::prompt_password(prompt)
+        .map(Zeroizing::new)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn read_password_fd(fd: i32) -> Result<Zeroizing<String>, String> {
+    use std::os::unix::io::FromRawFd;
+
+    let mut remaining = FD_PASSWORDS.lock().unwrap();
+    if remaining.is_none() {
+        // the descriptor is handed to us by the parent process; it is read to
+        // the end and closed on first use, later calls take the next line
+        let mut file = unsafe { fs::File::from_raw_fd(fd) };
+        let mut contents = Zeroizing::new(String::new());
+        file.read_to_string(&mut contents).map_err(|e| e.to_string())?;
+        *remaining = Some(contents);
+    }
+
+    let buffer = remaining.as_mut().unwrap();
+    if buffer.is_empty() {
+        return Err("no password left on KEYSTORE_PASSWORD_FD".to_string());
+    }
+    let end = buffer.find('\n').map_or(buffer.len(), |i| i + 1);
+    let line = Zeroizing::new(buffer[..end].trim_end_matches(&['\r', '\n'][..]).to_string());
+    buffer.replace_range(..end, "");
+    Ok(line)
+}
+
+#[cfg(not(unix))]
+fn read_password_fd(_fd: i32) -> Result<Zeroizing<String>, String> {
+    Err("KEYSTORE_PASSWORD_FD is only supported on unix".to_string())
+}
+
+fn unlock_keystore(path: &Path, keystore: &Keystore) -> Result<Keypair, String> {
+    let mut cached = CACHED_PASSWORD.lock().unwrap();
+    if let Some(password) = cached.as_ref() {
+        if let Ok(keypair) = decrypt_keystore(keystore, password) {
+            return Ok(keypair);
+        }
+    }
+
+    let password = read_password(&format!("Password for {} ({}): ", path.display(), keystore.pubkey))?;
+    let keypair = decrypt_keystore(keystore, &password)?;
+    *cached = Some(password);
+    Ok(keypair)
+}
+
+/// Drops the cached keystore password once all signers are loaded.
+pub fn forget_password() {
+    CACHED_PASSWORD.lock().unwrap().take();
+    // the descriptor is closed by now, so an emptied buffer stays behind
+    let mut remaining = FD_PASSWORDS.lock().unwrap();
+    if remaining.is_some() {
+        *remaining = Some(Zeroizing::new(String::new()));
+    }
+}
+
+/// Loads a Solana CLI JSON keypair file or an encrypted keystore.
+pub fn load_keypair(path: &Path) -> Result<Keypair, String> {
+    let contents = Zeroizing::new(fs::read_to_string(path).map_err(|e| e.to_string())?);
+
+    if contents.trim_start().starts_with('[') {
+        let bytes: Zeroizing<Vec<u8>> =
+            Zeroizing::new(serde_json::from_str(&contents).map_err(|e| e.to_string())?);
+        return Keypair::from_bytes(&bytes).map_err(|e| e.to_string());
+    }
+
+    let keystore: Keystore = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    unlock_keystore(path, &keystore)
+}
+
+/// The treasury / default signer: `PAYER_FILE` (keypair file or keystore) or
+/// the legacy base58 `PAYER`, which is removed from the environment once read.
+pub fn load_payer() -> Keypair {
+    if let Ok(path) = env::var("PAYER_FILE") {
+        return load_keypair(Path::new(&path))
+            .unwrap_or_else(|e| panic!("Failed to load PAYER_FILE {}: {}", path, e));
+    }
+
+    let payer_key = Zeroizing::new(env::var("PAYER").expect("PAYER or PAYER_FILE must be set in .env file"));
+    env::remove_var("PAYER");
+    Keypair::from_base58_string(&payer_key)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::io::IntoRawFd;
+
+    #[test]
+    fn password_fd_is_read_once_and_handed_out_line_by_line() {
+        let path = env::temp_dir().join(format!("sniper-password-fd-{}", std::process::id()));
+        fs::write(&path, "first\nsecond\r\n").unwrap();
+        let fd = fs::File::open(&path).unwrap().into_raw_fd();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(read_password_fd(fd).unwrap().as_str(), "first");
+        assert_eq!(read_password_fd(fd).unwrap().as_str(), "second");
+        assert!(read_password_fd(fd).is_err());
+    }
+}
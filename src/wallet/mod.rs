@@ -0,0 +1,5 @@
+pub mod balance;
+pub mod funding;
+pub mod keys;
+pub mod pool;
+pub mod reclaim;
@@ -0,0 +1,156 @@
+use std::env;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use super::balance::{BalanceConfig, BalanceService};
+use super::keys::load_keypair;
+use crate::utils::positions::PositionBook;
+
+#[derive(Clone, Copy, Debug)]
+pub enum RotationPolicy {
+    /// Each new position goes to the next wallet in turn.
+    RoundRobin,
+    /// New positions go to the wallet with the least SOL in open positions.
+    LeastExposed,
+    /// Every strategy instance trades from its own wallet.
+    PerStrategy,
+}
+
+impl RotationPolicy {
+    pub fn from_env() -> Self {
+        match env::var("wallet_rotation").as_deref() {
+            Ok("least_exposed") => RotationPolicy::LeastExposed,
+            Ok("per_strategy") => RotationPolicy::PerStrategy,
+            Ok("round_robin") | Err(_) => RotationPolicy::RoundRobin,
+            Ok(other) => panic!("wallet_rotation must be round_robin, least_exposed or per_strategy, got {}", other),
+        }
+    }
+}
+
+pub struct TradingWallet {
+    pub keypair: Arc<Keypair>,
+    pub pubkey: Pubkey,
+    /// `None` when replaying.
+    pub balance: Option<Arc<BalanceService>>,
+}
+
+#[derive(Debug)]
+pub struct WalletStatus {
+    pub pubkey: Pubkey,
+    pub open_positions: usize,
+    pub exposure_lamports: u64,
+    pub lamports: Option<u64>,
+}
+
+/// The wallets positions are opened from. A position stays with the wallet
+/// that bought it, so sells always sign with the keypair holding the tokens.
+pub struct WalletPool {
+    wallets: Vec<TradingWallet>,
+    policy: RotationPolicy,
+    next: AtomicUsize,
+}
+
+impl WalletPool {
+    pub fn new(keypairs: Vec<Arc<Keypair>>, policy: RotationPolicy) -> Self {
+        assert!(!keypairs.is_empty(), "wallet pool needs at least one keypair");
+        let wallets = keypairs
+            .into_iter()
+            .map(|keypair| TradingWallet {
+                pubkey: keypair.pubkey(),
+                keypair,
+                balance: None,
+            })
+            .collect();
+
+        WalletPool {
+            wallets,
+            policy,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Loads the keypair files or keystores listed in `wallet_files`, or trades from `payer`
+    /// alone when it is not set.
+    pub fn from_env(payer: Arc<Keypair>) -> Self {
+        let keypairs = match env::var("wallet_files") {
+            Ok(files) => files
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(|path| {
+                    let keypair = load_keypair(Path::new(path))
+                        .unwrap_or_else(|e| panic!("Failed to read keypair {}: {}", path, e));
+                    Arc::new(keypair)
+                })
+                .collect(),
+            Err(_) => vec![payer],
+        };
+
+        WalletPool::new(keypairs, RotationPolicy::from_env())
+    }
+
+    /// Starts a balance service per wallet, for live trading.
+    pub async fn follow_balances(&mut self, config: BalanceConfig, client: Arc<RpcClient>, wss_url: &str) {
+        for wallet in self.wallets.iter_mut() {
+            let service =
+                BalanceService::spawn(config.clone(), client.clone(), wss_url.to_string(), wallet.pubkey).await;
+            wallet.balance = Some(service);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.wallets.len()
+    }
+
+    pub fn get(&self, idx: usize) -> &TradingWallet {
+        &self.wallets[idx]
+    }
+
+    pub fn wallets(&self) -> &[TradingWallet] {
+        &self.wallets
+    }
+
+    /// Picks the wallet for a new position of `strategy`.
+    pub fn pick(&self, strategy: usize, positions: &PositionBook) -> usize {
+        let n = self.wallets.len();
+        match self.policy {
+            RotationPolicy::RoundRobin => self.next.fetch_add(1, Ordering::SeqCst) % n,
+            RotationPolicy::PerStrategy => strategy % n,
+            RotationPolicy::LeastExposed => {
+                let mut exposure = vec![0u64; n];
+                for position in positions.all() {
+                    if position.wallet < n {
+                        exposure[position.wallet] += position.cost_lamports;
+                    }
+                }
+                // ties go to the next wallet in turn so idle wallets share the load
+                let start = self.next.fetch_add(1, Ordering::SeqCst);
+                (0..n)
+                    .map(|i| (start + i) % n)
+                    .min_by_key(|idx| exposure[*idx])
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    pub fn status(&self, positions: &PositionBook) -> Vec<WalletStatus> {
+        let open = positions.all();
+        self.wallets
+            .iter()
+            .enumerate()
+            .map(|(idx, wallet)| {
+                let held: Vec<_> = open.iter().filter(|p| p.wallet == idx).collect();
+                WalletStatus {
+                    pubkey: wallet.pubkey,
+                    open_positions: held.len(),
+                    exposure_lamports: held.iter().map(|p| p.cost_lamports).sum(),
+                    lamports: wallet.balance.as_ref().and_then(|b| b.lamports()),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::Local;
+use std::collections::HashSet;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, instruction::Instruction, packet::PACKET_DATA_SIZE,
+    program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction,
+};
+use spl_token::instruction::close_account;
+use spl_token::state::Account as TokenAccount;
+
+use crate::utils::constants::{ACCOUNT_LEN, TOKEN_PROGRAM_ID};
+use crate::utils::create_ix::{bonding_curve_ata, bonding_curve_pda, create_sell_ix};
+use crate::utils::layouts::BondingCurveLayoutPF;
+use crate::utils::pf_price::curve_sell_sol;
+
+// pump.fun sells are far heavier than closes, keep a few per transaction
+const MAX_SELLS_PER_TX: usize = 4;
+const SELL_UNIT_LIMIT: u32 = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct TokenAccountInfo {
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub lamports: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReclaimReport {
+    pub closed: usize,
+    pub dust_sold: usize,
+    pub skipped: usize,
+    pub transactions: usize,
+    pub reclaimed_lamports: u64,
+}
+
+/// Every SPL token account owned by `owner`.
+pub async fn token_accounts(client: &RpcClient, owner: &Pubkey) -> Result<Vec<TokenAccountInfo>, String> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(ACCOUNT_LEN as u64),
+            // owner sits right after the mint in the token account layout
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(32, &owner.to_bytes())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = client
+        .get_program_accounts_with_config(&TOKEN_PROGRAM_ID, config)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(accounts
+        .into_iter()
+        .filter_map(|(address, account)| {
+            let state = TokenAccount::unpack(&account.data).ok()?;
+            Some(TokenAccountInfo {
+                address,
+                mint: state.mint,
+                amount: state.amount,
+                lamports: account.lamports,
+            })
+        })
+        .collect())
+}
+
+/// What `amount` of `mint` sells for on its bonding curve, `None` once the
+/// curve is complete or unknown.
+async fn curve_value(client: &RpcClient, mint: &Pubkey, amount: u64) -> Option<u64> {
+    let data = client.get_account_data(&bonding_curve_pda(mint)).await.ok()?;
+    let curve = BondingCurveLayoutPF::decode_pump_fun_bonding_curve_info(data.get(8..)?);
+    if curve.complete_bool() {
+        return None;
+    }
+    Some(curve_sell_sol(
+        curve.get_virtual_sol_reserves(),
+        curve.get_virtual_token_reserves(),
+        amount,
+    ))
+}
+
+fn fits(payer: &Pubkey, ixs: &[Instruction]) -> bool {
+    let tx = Transaction::new_with_payer(ixs, Some(payer));
+    bincode::serialized_size(&tx)
+        .map(|size| size as usize <= PACKET_DATA_SIZE)
+        .unwrap_or(false)
+}
+
+/// Packs instruction groups into as few transactions as fit the packet size,
+/// never splitting a group and never putting more than `max_groups` in one.
+fn pack(payer: &Pubkey, prefix: &[Instruction], groups: Vec<Vec<Instruction>>, max_groups: usize) -> Vec<Vec<Instruction>> {
+    let mut txs = Vec::new();
+    let mut current = prefix.to_vec();
+    let mut count = 0;
+
+    for group in groups {
+        let mut candidate = current.clone();
+        candidate.extend(group.iter().cloned());
+        if count < max_groups && fits(payer, &candidate) {
+            current = candidate;
+            count += 1;
+            continue;
+        }
+
+        if count > 0 {
+            txs.push(current);
+        }
+        current = prefix.to_vec();
+        current.extend(group);
+        count = 1;
+    }
+
+    if count > 0 {
+        txs.push(current);
+    }
+    txs
+}
+
+/// Closes the wallet's empty token accounts, selling balances worth less than
+/// `dust_lamports` on their curve first. Accounts of `keep` mints are left
+/// alone. The report holds the expected recovery; failed transactions are
+/// logged, not subtracted.
+pub async fn reclaim_wallet(
+    client: &RpcClient,
+    wallet: &Keypair,
+    dust_lamports: u64,
+    keep: &HashSet<Pubkey>,
+    dry_run: bool,
+) -> ReclaimReport {
+    let owner = wallet.pubkey();
+    let mut report = ReclaimReport::default();
+
+    let accounts = match token_accounts(client, &owner).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("Failed to list token accounts of {}: {}", owner, e);
+            return report;
+        }
+    };
+
+    let mut closes = Vec::new();
+    let mut dust_sells = Vec::new();
+
+    for account in accounts {
+        if keep.contains(&account.mint) {
+            report.skipped += 1;
+            continue;
+        }
+        let close_ix = close_account(&TOKEN_PROGRAM_ID, &account.address, &owner, &owner, &[&owner]).unwrap();
+
+        if account.amount == 0 {
+            closes.push(vec![close_ix]);
+            report.closed += 1;
+            report.reclaimed_lamports += account.lamports;
+            continue;
+        }
+
+        if dust_lamports == 0 {
+            report.skipped += 1;
+            continue;
+        }
+        match curve_value(client, &account.mint, account.amount).await {
+            Some(value) if value < dust_lamports => {
+                let bc_pk = bonding_curve_pda(&account.mint);
+                let sell_ix = create_sell_ix(
+                    account.amount,
+                    0,
+                    account.mint,
+                    bc_pk,
+                    bonding_curve_ata(&bc_pk, &account.mint),
+                    account.address,
+                    wallet,
+                )
+                .unwrap();
+                dust_sells.push(vec![sell_ix, close_ix]);
+                report.dust_sold += 1;
+                report.closed += 1;
+                report.reclaimed_lamports += account.lamports + value;
+            }
+            _ => report.skipped += 1,
+        }
+    }
+
+    let sell_budget = [ComputeBudgetInstruction::set_compute_unit_limit(
+        SELL_UNIT_LIMIT * MAX_SELLS_PER_TX as u32,
+    )];
+    let mut txs = pack(&owner, &[], closes, usize::MAX);
+    txs.extend(pack(&owner, &sell_budget, dust_sells, MAX_SELLS_PER_TX));
+    report.transactions = txs.len();
+
+    if dry_run {
+        return report;
+    }
+
+    for ixs in txs {
+        let recent_blockhash = match client.get_latest_blockhash().await {
+            Ok(blockhash) => blockhash,
+            Err(e) => {
+                eprintln!("Failed to get blockhash: {:?}", e);
+                break;
+            }
+        };
+        let tx = Transaction::new_signed_with_payer(&ixs, Some(&owner), &[wallet], recent_blockhash);
+        match client.send_and_confirm_transaction(&tx).await {
+            Ok(sig) => println!(
+                "{}:: reclaim tx for {} with {} instructions: {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S"),
+                owner,
+                ixs.len(),
+                sig
+            ),
+            Err(e) => eprintln!("Reclaim tx for {} failed: {:?}", owner, e),
+        }
+    }
+
+    report
+}